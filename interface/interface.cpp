@@ -1,7 +1,16 @@
+#include <cstddef>
+#include <cstdlib>
 #include <dlfcn.h>
 #include <iostream>
 #include <vector>
 
+#include "rustbot.h"
+
+// A struct change on either side must not go unnoticed.
+static_assert(sizeof(rustbot_board_state) == 83 * sizeof(int32_t), "rustbot_board_state layout changed");
+static_assert(offsetof(rustbot_board_state, current) == 82 * sizeof(int32_t), "rustbot_board_state layout changed");
+static_assert(sizeof(rustbot_move) == 2 * sizeof(int32_t), "rustbot_move layout changed");
+
 enum piece {
     CROSS = 1,
     EMPTY = 0,
@@ -19,15 +28,11 @@ struct raw_boardstate {
     short current; 
 };
 
-extern "C" {
-    struct ffi_safe_boardstate {
-        piece board[9][9];
-        piece turn;
-        int current;
-    };
+static_assert(sizeof(_move) == sizeof(rustbot_move), "_move layout changed");
 
+extern "C" {
     _move bot(raw_boardstate boardstate) {
-        ffi_safe_boardstate ffi_safe_state = {};
+        rustbot_board_state ffi_safe_state = {};
         
         for (int i = 0; i < 9; i++) {
             for (int j = 0; j < 9; j++) {
@@ -39,17 +44,25 @@ extern "C" {
 
         ffi_safe_state.current = (int) boardstate.current;
 
-        void* handle = dlopen("./rustbot.so", RTLD_LAZY);
-
-        dlerror();
-
-        typedef _move (*bot_move_fn)(ffi_safe_boardstate);
-        bot_move_fn get_move = (bot_move_fn) dlsym(handle, "get_move");
-
-        _move move = get_move(ffi_safe_state);
+        // The library and its engine are kept for the whole game, so that the
+        // engine can reuse its search between moves and ponder in between.
+        static void* library = dlopen("./rustbot.so", RTLD_LAZY);
+        static bool loaded = [] {
+            typedef uint32_t (*abi_version_fn)();
+            abi_version_fn abi_version = library ? (abi_version_fn) dlsym(library, "rustbot_abi_version") : nullptr;
+            if (!abi_version || abi_version() != RUSTBOT_ABI_VERSION) {
+                std::cerr << "rustbot.so does not have ABI version " << RUSTBOT_ABI_VERSION << std::endl;
+                std::abort();
+            }
+            return true;
+        }();
+        (void) loaded;
+        typedef rustbot_engine* (*engine_new_fn)();
+        typedef rustbot_move (*engine_get_move_fn)(rustbot_engine*, rustbot_board_state);
+        static engine_get_move_fn engine_get_move = (engine_get_move_fn) dlsym(library, "rustbot_engine_get_move");
+        static rustbot_engine* engine = ((engine_new_fn) dlsym(library, "rustbot_engine_new"))();
 
-        dlclose(handle);
-        
-        return move;
+        rustbot_move move = engine_get_move(engine, ffi_safe_state);
+        return _move { move.sub, move.spot };
     };
-}
\ No newline at end of file
+}
@@ -0,0 +1,57 @@
+// The C ABI of rustbot.so. Kept by hand in step with `src/ffi.rs`, whose
+// tests check the version, the symbols and the struct layouts below.
+#ifndef RUSTBOT_H
+#define RUSTBOT_H
+
+#include <stdint.h>
+
+// Raised whenever an exported function or struct changes. Compare it with
+// `rustbot_abi_version()` after loading the library.
+#define RUSTBOT_ABI_VERSION 1
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Subboards and squares are numbered 0 to 8 from the top left.
+typedef struct rustbot_board_state {
+    int32_t board[9][9]; // [subboard][square]: 1 for cross, 0 for empty, -1 for dot.
+    int32_t turn;        // 1 for cross, -1 for dot.
+    int32_t current;     // The subboard that must be played on, or -1 for any.
+} rustbot_board_state;
+
+typedef struct rustbot_move {
+    int32_t sub;
+    int32_t spot;
+} rustbot_move;
+
+// An engine that keeps its search between moves. Only used through pointers.
+typedef struct rustbot_engine rustbot_engine;
+
+uint32_t rustbot_abi_version(void);
+
+// The engine, its time limit and the log file are read once from rustbot.json
+// next to the library, and from RUSTBOT_* environment variables. See `Config`
+// in `src/ffi/config.rs`.
+
+// Chooses a move with the configured engine, which is kept between calls,
+// always returning a move on the board.
+rustbot_move get_move(rustbot_board_state board_state);
+
+// Starts from the configured engine. Returns null if it could not be created.
+rustbot_engine* rustbot_engine_new(void);
+
+// Returns 0 once the option is set, and -1 otherwise. The options are listed
+// at `EngineHandle` in `src/ffi.rs`.
+int rustbot_engine_set_option(rustbot_engine* engine, const char* name, const char* value);
+
+rustbot_move rustbot_engine_get_move(rustbot_engine* engine, rustbot_board_state board_state);
+
+// Stops pondering and frees the engine. Must be called before unloading the library.
+void rustbot_engine_free(rustbot_engine* engine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
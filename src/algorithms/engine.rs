@@ -0,0 +1,222 @@
+use std::{error::Error, fmt::Display, sync::{atomic::AtomicBool, Arc}, time::Duration};
+
+use crate::{algorithms::{greedy::GreedyEngine, mcts::{MctsConfig, MctsEngine}, minimax::{debug::format_eval, eval::{Eval, plies_to_loss, plies_to_win}, MinimaxEngine, SearchConfig}}, utils::{Move, board_state::BoardState}};
+
+pub type ProgressCallback = Arc<dyn Fn(&SearchResult) + Send + Sync>;
+
+// What an engine may spend on one move. Engines ignore limits they have no use for.
+#[derive(Clone)]
+pub struct Limits {
+    pub time:        Option<Duration>,
+    pub depth:       Option<u32>, // In plies.
+    pub nodes:       Option<u64>,
+    pub stop:        Option<Arc<AtomicBool>>, // Ends the search early once set.
+    pub on_progress: Option<ProgressCallback>, // Called with intermediate results.
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            time:        Some(Duration::from_millis(1_000)),
+            depth:       None,
+            nodes:       None,
+            stop:        None,
+            on_progress: None,
+        }
+    }
+}
+
+// The outcome of a search for one move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub eval: Eval, // For the player to move, in the units of the minimax eval.
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub principal_variation: Vec<Move>, // Starts with `best_move`.
+}
+
+impl SearchResult {
+    // The number of plies until the searching player wins, if the win is forced.
+    pub fn win_in(&self) -> Option<u32> {
+        plies_to_win(self.eval)
+    }
+
+    // The number of plies until the searching player loses, if the loss is forced.
+    pub fn loss_in(&self) -> Option<u32> {
+        plies_to_loss(self.eval)
+    }
+
+    pub fn dbg_print(&self) {
+        eprintln!(
+            "depth: {}, eval: {}, nodes: {}, time: {}ms",
+            self.depth,
+            format_eval(self.eval),
+            self.nodes,
+            self.elapsed.as_millis(),
+        );
+        for (index, move_) in self.principal_variation.iter().enumerate() {
+            eprintln!("{:>2}: {}", index + 1, move_.dbg_to_string());
+        }
+    }
+}
+
+// A bot that plays one side of a game. Engines may keep state between the
+// moves of a game, which `new_game` throws away.
+pub trait Engine: Send {
+    fn name(&self) -> &str;
+
+    fn choose_move(&mut self, board_state: &BoardState, limits: &Limits) -> SearchResult;
+
+    fn new_game(&mut self) {}
+}
+
+// Lets borrowed engines be handed to a `client::Game`.
+impl<E: Engine + ?Sized> Engine for &mut E {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn choose_move(&mut self, board_state: &BoardState, limits: &Limits) -> SearchResult {
+        (**self).choose_move(board_state, limits)
+    }
+
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+}
+
+pub const ENGINE_NAMES: [&str; 3] = ["greedy", "minimax", "mcts"];
+
+pub fn engine_by_name(name: &str) -> Option<Box<dyn Engine>> {
+    match name {
+        "greedy"  => Some(Box::new(GreedyEngine)),
+        "minimax" => Some(Box::new(MinimaxEngine::default())),
+        "mcts"    => Some(Box::new(MctsEngine::default())),
+        _         => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineSpecError {
+    UnknownEngine(String),
+    UnknownOption(String),
+    InvalidValue(String),
+}
+
+impl Display for EngineSpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownEngine(name)   => write!(f, "unknown engine \"{}\"", name),
+            Self::UnknownOption(option) => write!(f, "unknown engine option \"{}\"", option),
+            Self::InvalidValue(option)  => write!(f, "invalid value for engine option \"{}\"", option),
+        }
+    }
+}
+
+impl Error for EngineSpecError {}
+
+// Parses an engine name optionally followed by options, as in
+// "minimax:depth=4,threads=2,tt=64" or "mcts:exploration=1.0,seed=7".
+pub fn engine_from_spec(spec: &str) -> Result<Box<dyn Engine>, EngineSpecError> {
+    let (name, options) = spec.split_once(':').unwrap_or((spec, ""));
+    let mut options = options
+        .split(',')
+        .filter(|option| !option.is_empty())
+        .map(|option| option.split_once('=').ok_or_else(|| EngineSpecError::InvalidValue(option.to_owned())));
+
+    match name {
+        "greedy" => {
+            if let Some(option) = options.next() {
+                let (key, _) = option?;
+                return Err(EngineSpecError::UnknownOption(key.to_owned()));
+            }
+            Ok(Box::new(GreedyEngine))
+        },
+        "minimax" => {
+            let mut config = SearchConfig::default();
+            for option in options {
+                let (key, value) = option?;
+                match key {
+                    "depth"   => config.max_depth_plies = parse_option(key, value)?,
+                    "threads" => config.threads = parse_option(key, value)?,
+                    "tt"      => config.transposition_table_size_mb = parse_option(key, value)?,
+                    _         => return Err(EngineSpecError::UnknownOption(key.to_owned())),
+                }
+            }
+            Ok(Box::new(MinimaxEngine::new(config)))
+        },
+        "mcts" => {
+            let mut config = MctsConfig::default();
+            for option in options {
+                let (key, value) = option?;
+                match key {
+                    "exploration" => config.exploration = parse_option(key, value)?,
+                    "seed"        => config.seed = parse_option(key, value)?,
+                    _             => return Err(EngineSpecError::UnknownOption(key.to_owned())),
+                }
+            }
+            Ok(Box::new(MctsEngine::new(config)))
+        },
+        _ => Err(EngineSpecError::UnknownEngine(name.to_owned())),
+    }
+}
+
+fn parse_option<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, EngineSpecError> {
+    value.parse().map_err(|_| EngineSpecError::InvalidValue(key.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{board_state::BoardState, pattern::PatternState, Player};
+
+    use super::{engine_by_name, engine_from_spec, EngineSpecError, Limits, ENGINE_NAMES};
+
+    #[test]
+    fn engines_play_legal_games() {
+        let limits = Limits {
+            time: None,
+            depth: Some(2),
+            nodes: Some(200),
+            ..Limits::default()
+        };
+        for cross_name in ENGINE_NAMES {
+            for dot_name in ENGINE_NAMES {
+                let mut cross = engine_by_name(cross_name).unwrap();
+                let mut dot = engine_by_name(dot_name).unwrap();
+                assert_eq!(cross.name(), cross_name);
+                cross.new_game();
+                dot.new_game();
+
+                let mut board_state = BoardState::new_empty(Player::Cross);
+                while board_state.state() == PatternState::Undecided {
+                    let engine = match board_state.turn() {
+                        Player::Cross => &mut cross,
+                        Player::Dot   => &mut dot,
+                    };
+                    let result = engine.choose_move(&board_state, &limits);
+                    assert_eq!(result.principal_variation.first(), Some(&result.best_move));
+                    board_state = board_state.try_do_move(result.best_move).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_engine() {
+        assert!(engine_by_name("random").is_none());
+    }
+
+    #[test]
+    fn engine_from_spec_options() {
+        assert_eq!(engine_from_spec("minimax:depth=3,threads=2,tt=1").unwrap().name(), "minimax");
+        assert_eq!(engine_from_spec("mcts:exploration=0.5,seed=3").unwrap().name(), "mcts");
+        assert_eq!(engine_from_spec("greedy").unwrap().name(), "greedy");
+        assert_eq!(engine_from_spec("random").err(), Some(EngineSpecError::UnknownEngine("random".to_owned())));
+        assert_eq!(engine_from_spec("greedy:depth=3").err(), Some(EngineSpecError::UnknownOption("depth".to_owned())));
+        assert_eq!(engine_from_spec("minimax:width=3").err(), Some(EngineSpecError::UnknownOption("width".to_owned())));
+        assert_eq!(engine_from_spec("minimax:depth=x").err(), Some(EngineSpecError::InvalidValue("depth".to_owned())));
+        assert_eq!(engine_from_spec("mcts:seed").err(), Some(EngineSpecError::InvalidValue("seed".to_owned())));
+    }
+}
@@ -1,4 +1,6 @@
-use crate::utils::{Centeredness, Move, Place, board_state::BoardState};
+use std::time::Instant;
+
+use crate::{algorithms::{engine::{Engine, Limits, SearchResult}, minimax::eval::{eval, eval_at_ply}}, utils::{Centeredness, Move, Place, board_state::BoardState}};
 
 // "greedy" chooses, in order of priority:
 // * A move that wins.
@@ -53,6 +55,29 @@ pub fn greedy(board_state: &BoardState) -> Move {
     panic!("no eligible moves");
 }
 
+pub struct GreedyEngine;
+
+impl Engine for GreedyEngine {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    // Greedy does not search, so its eval is the static eval of the position
+    // after its move, from its own side.
+    fn choose_move(&mut self, board_state: &BoardState, _limits: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let move_ = greedy(board_state);
+        SearchResult {
+            best_move: move_,
+            eval: eval_at_ply(-eval(&board_state.do_move(move_)), 1),
+            depth: 1,
+            nodes: 1,
+            elapsed: start.elapsed(),
+            principal_variation: vec![move_],
+        }
+    }
+}
+
 fn best_subboard_winning_move(board_state: &BoardState, eligible_moves: &[Move]) -> Option<Move> {
     let mut subboard_winning_moves: Vec<_> = eligible_moves
         .iter()
@@ -110,3 +135,36 @@ fn cmp_centeredness(place1: Place, place2: Place) -> std::cmp::Ordering {
         _                                            => std::cmp::Ordering::Less,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{algorithms::{engine::{Engine, Limits}, minimax::eval::eval}, utils::{board_state::BoardState, Player}};
+
+    use super::GreedyEngine;
+
+    #[test]
+    fn engine_eval() {
+        // Cross wins by taking the top right subboard.
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X  ",
+                "     .     .O O  ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .  O  .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], 2, "cross",
+        );
+        let result = GreedyEngine.choose_move(&board_state, &Limits::default());
+        assert_eq!(result.win_in(), Some(1));
+
+        let board_state = BoardState::new_empty(Player::Cross);
+        let result = GreedyEngine.choose_move(&board_state, &Limits::default());
+        assert_eq!(result.eval, -eval(&board_state.do_move(result.best_move)));
+    }
+}
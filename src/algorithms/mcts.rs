@@ -0,0 +1,348 @@
+use std::{sync::atomic::{AtomicBool, Ordering}, time::{Duration, Instant}};
+
+use crate::{algorithms::{engine::{Engine, Limits, SearchResult}, minimax::{eval::{EVAL_WON, Eval}, SearchConfig}}, utils::{Move, Player, board_state::BoardState, pattern::PatternState, rng::Rng}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsConfig {
+    pub time_limit: Option<Duration>,
+    pub max_iterations: Option<u64>,
+    pub exploration: f64,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            // Same budget as minimax, so the two can be compared move for move.
+            time_limit: SearchConfig::default().time_limit,
+            max_iterations: None,
+            exploration: std::f64::consts::SQRT_2,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsResult {
+    pub best_move: Move,
+    pub win_rate: f64, // For the player to move, with draws counting as half a win.
+    pub visits: u64, // Of `best_move`.
+    pub iterations: u64,
+    pub elapsed: Duration,
+    pub principal_variation: Vec<Move>, // The most visited line, starting with `best_move`.
+}
+
+impl MctsResult {
+    fn into_search_result(self) -> SearchResult {
+        SearchResult {
+            best_move: self.best_move,
+            // Win rates are mapped onto half the eval range, as they never prove a result.
+            eval: (self.win_rate as Eval - 0.5) * EVAL_WON,
+            depth: self.principal_variation.len() as u32,
+            nodes: self.iterations,
+            elapsed: self.elapsed,
+            principal_variation: self.principal_variation,
+        }
+    }
+
+    pub fn dbg_print(&self) {
+        eprintln!(
+            "win rate: {:.3}, visits: {}/{}, time: {}ms",
+            self.win_rate,
+            self.visits,
+            self.iterations,
+            self.elapsed.as_millis(),
+        );
+    }
+}
+
+struct Node {
+    board_state:   BoardState,
+    move_:         Option<Move>, // The move leading to this node, `None` at the root.
+    parent:        Option<usize>,
+    children:      Vec<usize>,
+    untried_moves: Vec<Move>,
+    visits:        u64,
+    score:         f64, // For the player who made `move_`.
+}
+
+impl Node {
+    fn new(board_state: BoardState, move_: Option<Move>, parent: Option<usize>) -> Self {
+        let untried_moves = if board_state.state() == PatternState::Undecided {
+            board_state.eligible_moves().into_vec()
+        } else {
+            Vec::new()
+        };
+        Self {
+            board_state,
+            move_,
+            parent,
+            children: Vec::new(),
+            untried_moves,
+            visits: 0,
+            score: 0.0,
+        }
+    }
+}
+
+pub fn mcts(board_state: &BoardState) -> Move {
+    mcts_with_config(board_state, &MctsConfig::default()).best_move
+}
+
+// UCT with uniformly random playouts. Nodes are kept in one vector and refer to
+// each other by index.
+pub fn mcts_with_config(board_state: &BoardState, config: &MctsConfig) -> MctsResult {
+    search(board_state, config, None, None)
+}
+
+// How often a search reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+fn search(
+    board_state:   &BoardState,
+    config:        &MctsConfig,
+    external_stop: Option<&AtomicBool>,
+    on_progress:   Option<&dyn Fn(&MctsResult)>,
+) -> MctsResult {
+    let start = Instant::now();
+    let mut last_progress = start;
+    let deadline = config.time_limit.map(|time_limit| start + time_limit);
+    let mut rng = Rng::new(config.seed);
+    let mut nodes = vec![Node::new(*board_state, None, None)];
+
+    if nodes[0].untried_moves.is_empty() {
+        panic!("no eligible moves");
+    }
+
+    let mut iterations = 0;
+    loop {
+        let iterations_exceeded = config.max_iterations
+            .is_some_and(|max_iterations| iterations >= max_iterations);
+        let now = Instant::now();
+        let time_exceeded = deadline.is_some_and(|deadline| now >= deadline);
+        let stopped = external_stop.is_some_and(|external_stop| external_stop.load(Ordering::Relaxed));
+        // At least one iteration is needed to have a move.
+        if iterations > 0 && (iterations_exceeded || time_exceeded || stopped) {
+            break;
+        }
+        if let Some(on_progress) = on_progress && now - last_progress >= PROGRESS_INTERVAL {
+            on_progress(&result(&nodes, iterations, start));
+            last_progress = now;
+        }
+
+        let mut index = select(&nodes, config.exploration);
+
+        if !nodes[index].untried_moves.is_empty() {
+            let untried_moves = &mut nodes[index].untried_moves;
+            let move_ = untried_moves.swap_remove(rng.below(untried_moves.len()));
+            let child = Node::new(nodes[index].board_state.do_move(move_), Some(move_), Some(index));
+            nodes.push(child);
+            let child_index = nodes.len() - 1;
+            nodes[index].children.push(child_index);
+            index = child_index;
+        }
+
+        let winner = playout(nodes[index].board_state, &mut rng);
+
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let node = &mut nodes[index];
+            node.visits += 1;
+            // The root has no move, and its score is never read.
+            let mover = node.board_state.turn().opposite();
+            node.score += match winner {
+                Some(winner) if winner == mover => 1.0,
+                Some(_)                         => 0.0,
+                None                            => 0.5,
+            };
+            current = node.parent;
+        }
+
+        iterations += 1;
+    }
+
+    result(&nodes, iterations, start)
+}
+
+fn result(nodes: &[Node], iterations: u64, start: Instant) -> MctsResult {
+    let best_child = most_visited_child(nodes, &nodes[0])
+        .expect("root has children after one iteration");
+
+    MctsResult {
+        best_move: best_child.move_.expect("child nodes have moves"),
+        win_rate: best_child.score / best_child.visits as f64,
+        visits: best_child.visits,
+        iterations,
+        elapsed: start.elapsed(),
+        principal_variation: principal_variation(nodes),
+    }
+}
+
+fn most_visited_child<'a>(nodes: &'a [Node], node: &Node) -> Option<&'a Node> {
+    node.children
+        .iter()
+        .map(|&index| &nodes[index])
+        .max_by_key(|child| child.visits)
+}
+
+fn principal_variation(nodes: &[Node]) -> Vec<Move> {
+    let mut principal_variation = Vec::new();
+    let mut node = &nodes[0];
+    while let Some(child) = most_visited_child(nodes, node) {
+        principal_variation.push(child.move_.expect("child nodes have moves"));
+        node = child;
+    }
+    principal_variation
+}
+
+// Holds the generator between moves, so that consecutive searches differ.
+pub struct MctsEngine {
+    config: MctsConfig,
+    rng:    Rng,
+}
+
+impl MctsEngine {
+    pub fn new(config: MctsConfig) -> Self {
+        let rng = Rng::new(config.seed);
+        Self {
+            config,
+            rng,
+        }
+    }
+}
+
+impl Default for MctsEngine {
+    fn default() -> Self {
+        Self::new(MctsConfig::default())
+    }
+}
+
+impl Engine for MctsEngine {
+    fn name(&self) -> &str {
+        "mcts"
+    }
+
+    fn choose_move(&mut self, board_state: &BoardState, limits: &Limits) -> SearchResult {
+        let config = MctsConfig {
+            time_limit: limits.time,
+            max_iterations: limits.nodes,
+            seed: self.rng.next_u64(),
+            ..self.config.clone()
+        };
+        let on_progress = limits.on_progress.as_ref().map(|on_progress| {
+            move |result: &MctsResult| on_progress(&result.clone().into_search_result())
+        });
+        search(
+            board_state, &config,
+            limits.stop.as_deref(),
+            on_progress.as_ref().map(|on_progress| on_progress as &dyn Fn(&MctsResult)),
+        ).into_search_result()
+    }
+
+    fn new_game(&mut self) {
+        self.rng = Rng::new(self.config.seed);
+    }
+}
+
+// Descends from the root until reaching a node that is terminal or not fully expanded.
+fn select(nodes: &[Node], exploration: f64) -> usize {
+    let mut index = 0;
+    loop {
+        let node = &nodes[index];
+        if !node.untried_moves.is_empty() || node.children.is_empty() {
+            return index;
+        }
+        let log_visits = (node.visits as f64).ln();
+        index = *node.children
+            .iter()
+            .max_by(|&&child1, &&child2| {
+                let uct1 = uct(&nodes[child1], log_visits, exploration);
+                let uct2 = uct(&nodes[child2], log_visits, exploration);
+                uct1.total_cmp(&uct2)
+            })
+            .expect("children checked to be non-empty");
+    }
+}
+
+fn uct(node: &Node, parent_log_visits: f64, exploration: f64) -> f64 {
+    let visits = node.visits as f64;
+    node.score / visits + exploration * (parent_log_visits / visits).sqrt()
+}
+
+// Plays random moves until the game ends, returning the winner.
+fn playout(mut board_state: BoardState, rng: &mut Rng) -> Option<Player> {
+    loop {
+        match board_state.state() {
+            PatternState::Won(player) => return Some(player),
+            PatternState::Drawn       => return None,
+            PatternState::Undecided   => (),
+        }
+        let eligible_moves = board_state.eligible_moves();
+        board_state = board_state.do_move(*rng.choose(&eligible_moves));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::utils::{board_state::BoardState, Move, Place, Player, Spot};
+
+    use super::{mcts_with_config, MctsConfig};
+
+    #[test]
+    fn max_iterations() {
+        let board_state = BoardState::new_empty(Player::Cross);
+        let config = MctsConfig {
+            time_limit: None,
+            max_iterations: Some(500),
+            ..MctsConfig::default()
+        };
+        let result = mcts_with_config(&board_state, &config);
+        assert_eq!(result.iterations, 500);
+        assert!(board_state.eligible_moves().contains(&result.best_move));
+        assert!((0.0..=1.0).contains(&result.win_rate));
+
+        // The same seed gives the same search.
+        assert_eq!(mcts_with_config(&board_state, &config).best_move, result.best_move);
+    }
+
+    #[test]
+    fn time_limit() {
+        let board_state = BoardState::new_empty(Player::Dot);
+        let config = MctsConfig {
+            time_limit: Some(Duration::from_millis(50)),
+            ..MctsConfig::default()
+        };
+        let result = mcts_with_config(&board_state, &config);
+        assert!(result.iterations > 0);
+        assert!(result.elapsed < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn finds_win() {
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X  ",
+                "     .     .O O  ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .  O  .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], 2, "cross",
+        );
+        let config = MctsConfig {
+            time_limit: None,
+            max_iterations: Some(2_000),
+            ..MctsConfig::default()
+        };
+        let result = mcts_with_config(&board_state, &config);
+        assert_eq!(result.best_move, Move::new(Spot { subboard: Place::TopRig, square: Place::TopRig }));
+        assert!(result.win_rate > 0.99);
+    }
+}
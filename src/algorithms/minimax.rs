@@ -1,103 +1,330 @@
-use std::{sync::mpsc, thread, time::{Duration, Instant}};
+use std::{panic, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, mpsc::{self, RecvTimeoutError}, Arc}, thread, time::{Duration, Instant}};
 
-use crate::{algorithms::minimax::{eval::{EVAL_LOST, EVAL_WON, Eval, eval}, transposition_table::{TranspositionTable, TranspositionTableResponse}}, utils::{Move, board_state::BoardState, pattern::PatternState}};
+use crate::{algorithms::{engine::{Engine, Limits, SearchResult}, minimax::{eval::{EVAL_LOST, EVAL_WON, Eval, eval, eval_at_ply, is_loss, is_win}, transposition_table::{Bound, TranspositionTable, TranspositionTableResponse}}}, utils::{Move, board_state::BoardState, pattern::PatternState}};
 
-mod eval;
+pub(crate) mod eval;
 mod transposition_table;
 pub mod debug;
 
-const MAX_DEPTH_PLIES: u32 = 99;
-const MAX_SEARCH_TIME_MILLIS: u64 = 1_000;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub time_limit: Option<Duration>,
+    pub max_depth_plies: u32,
+    pub max_nodes: Option<u64>,
+    pub transposition_table_size_mb: usize,
+    pub threads: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            time_limit: Some(Duration::from_millis(1_000)),
+            max_depth_plies: 99,
+            max_nodes: None,
+            transposition_table_size_mb: 32,
+            threads: 1,
+        }
+    }
+}
 
 enum Message {
-    BestYetMove(Move),
-    SearchTerminatedMove(Move),
-    TimeUp,
+    BestYetResult(SearchResult),
+    SearchTerminatedResult(SearchResult),
+}
+
+// State shared by all nodes of one search thread.
+struct SearchContext<'a> {
+    transposition_table: &'a TranspositionTable,
+    stop:                &'a AtomicBool,
+    nodes:               &'a AtomicU64, // Shared between threads.
+    max_nodes:           Option<u64>,
+    interruptible:       bool,
+}
+
+impl SearchContext<'_> {
+    fn should_stop(&self) -> bool {
+        if !self.interruptible {
+            return false;
+        }
+        let nodes_exceeded = self.max_nodes
+            .is_some_and(|max_nodes| self.nodes.load(Ordering::Relaxed) >= max_nodes);
+        nodes_exceeded || self.stop.load(Ordering::Relaxed)
+    }
 }
 
+// One-shot helpers: every call allocates a new transposition table, so nothing
+// is kept between moves. Keep a `MinimaxEngine` to reuse the table instead.
 pub fn minimax(board_state: &BoardState) -> Move {
-    let mut transposition_table = TranspositionTable::new();
+    minimax_with_config(board_state, &SearchConfig::default()).best_move
+}
+
+pub fn minimax_with_config(board_state: &BoardState, config: &SearchConfig) -> SearchResult {
+    MinimaxEngine::new(config.clone()).search(board_state)
+}
+
+// Keeps its transposition table between moves of a game.
+pub struct MinimaxEngine {
+    config:              SearchConfig,
+    transposition_table: Arc<TranspositionTable>,
+}
+
+impl MinimaxEngine {
+    pub fn new(config: SearchConfig) -> Self {
+        let transposition_table = Arc::new(TranspositionTable::new(config.transposition_table_size_mb));
+        Self {
+            config,
+            transposition_table,
+        }
+    }
+
+    // Searches within the limits of the engine's own config.
+    pub fn search(&mut self, board_state: &BoardState) -> SearchResult {
+        let config = self.config.clone();
+        self.search_with(board_state, &config, None, None)
+    }
+
+    fn search_with(
+        &mut self,
+        board_state: &BoardState,
+        config:      &SearchConfig,
+        stop:        Option<&AtomicBool>,
+        on_progress: Option<&(dyn Fn(&SearchResult) + Send + Sync)>,
+    ) -> SearchResult {
+        search(board_state, config, Arc::clone(&self.transposition_table), stop, on_progress)
+    }
+}
+
+impl Default for MinimaxEngine {
+    fn default() -> Self {
+        Self::new(SearchConfig::default())
+    }
+}
+
+impl Engine for MinimaxEngine {
+    fn name(&self) -> &str {
+        "minimax"
+    }
+
+    fn choose_move(&mut self, board_state: &BoardState, limits: &Limits) -> SearchResult {
+        let config = SearchConfig {
+            time_limit: limits.time,
+            max_depth_plies: limits.depth.unwrap_or(self.config.max_depth_plies),
+            max_nodes: limits.nodes,
+            ..self.config.clone()
+        };
+        self.search_with(board_state, &config, limits.stop.as_deref(), limits.on_progress.as_deref())
+    }
+
+    fn new_game(&mut self) {
+        self.transposition_table = Arc::new(TranspositionTable::new(self.config.transposition_table_size_mb));
+    }
+}
+
+// How often a search with an external stop flag checks it.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// Searches with a transposition table that may hold entries from earlier searches.
+// Every completed depth but the last is passed to `on_progress`.
+fn search(
+    board_state:         &BoardState,
+    config:              &SearchConfig,
+    transposition_table: Arc<TranspositionTable>,
+    external_stop:       Option<&AtomicBool>,
+    on_progress:         Option<&(dyn Fn(&SearchResult) + Send + Sync)>,
+) -> SearchResult {
+    let start = Instant::now();
+    let deadline = config.time_limit.map(|time_limit| start + time_limit);
+    let stop = Arc::new(AtomicBool::new(false));
+    let nodes = Arc::new(AtomicU64::new(0));
+    transposition_table.new_search();
 
     let (tx, rx) = mpsc::channel();
 
-    let tx_minimax = tx.clone();
     let board_state = *board_state;
-    thread::spawn(move || {
-        let mut depth = 1;
-        loop {
-            minimax_inner(
-                &board_state, &mut transposition_table,
-                depth, true,
-                EVAL_LOST - 1.0, EVAL_WON + 1.0,
-            );
-            let TranspositionTableResponse::PresentHighDepth {
-                best_move: Some(move_),
-                eval,
-            } = transposition_table.get(&board_state, 0) else {
-                panic!("no eligible move");
-            };
-            if depth == MAX_DEPTH_PLIES ||
-               eval == EVAL_WON ||
-               eval == EVAL_LOST {
-                tx_minimax.send(Message::SearchTerminatedMove(move_)).expect("failed to send search terminated move message to main thread");
-                break;
-            }
-            tx_minimax.send(Message::BestYetMove(move_)).expect("failed to send best yet move message to main thread");
-            depth += 1;
-        }
-    });
-
-    let tx_time = tx.clone();
-    let start_instant = Instant::now();
-    thread::spawn(move || {
-        loop {
-            thread::sleep(Duration::from_millis(10));
-            let elapsed = start_instant.elapsed();
-            if elapsed > Duration::from_millis(MAX_SEARCH_TIME_MILLIS) {
-                tx_time.send(Message::TimeUp).expect("failed to send time up message to main thread");
-            }
-        }
-    });
+    // Lazy SMP: all threads search the same position sharing the transposition
+    // table, with helpers starting at staggered depths. Only the first thread
+    // reports results.
+    let search_threads: Vec<_> = (0..config.threads.max(1))
+        .map(|thread_index| {
+            let stop = Arc::clone(&stop);
+            let nodes = Arc::clone(&nodes);
+            let transposition_table = Arc::clone(&transposition_table);
+            let tx = (thread_index == 0).then(|| tx.clone());
+            let config = config.clone();
+            thread::spawn(move || {
+                let max_depth_plies = config.max_depth_plies.max(1);
+                let mut depth = 1 + thread_index as u32 % 2;
+                let mut principal_variation = Vec::new();
+                while depth <= max_depth_plies {
+                    let context = SearchContext {
+                        transposition_table: &transposition_table,
+                        stop: &stop,
+                        nodes: &nodes,
+                        max_nodes: config.max_nodes,
+                        // The depth 1 move is always completed, so there is a move to return.
+                        interruptible: tx.is_none() || depth > 1,
+                    };
+                    // Once stopped, the unfinished depth is discarded.
+                    let Some(eval) = minimax_inner(
+                        &board_state, &context,
+                        depth, 0,
+                        EVAL_LOST - 1.0, EVAL_WON + 1.0,
+                        &mut principal_variation,
+                    ) else {
+                        break;
+                    };
+                    if let Some(tx) = &tx {
+                        let Some(&best_move) = principal_variation.first() else {
+                            panic!("no eligible move");
+                        };
+                        extend_principal_variation(&board_state, &transposition_table, &mut principal_variation, depth as usize);
+                        let result = SearchResult {
+                            best_move,
+                            eval,
+                            depth,
+                            nodes: nodes.load(Ordering::Relaxed),
+                            elapsed: start.elapsed(),
+                            principal_variation: principal_variation.clone(),
+                        };
+                        // The shortest forced win or longest forced loss is found
+                        // at the first depth it shows up in.
+                        let terminated = depth == max_depth_plies ||
+                            is_win(eval) ||
+                            is_loss(eval);
+                        let message = if terminated {
+                            Message::SearchTerminatedResult(result)
+                        } else {
+                            Message::BestYetResult(result)
+                        };
+                        // The receiver is only gone once the search is being stopped.
+                        if tx.send(message).is_err() || terminated {
+                            break;
+                        }
+                    }
+                    depth += 1;
+                }
+            })
+        })
+        .collect();
+    drop(tx);
 
-    let mut best_move_yet = None;
-    for message in rx.iter() {
+    // Waits for at least the depth 1 result, even if it arrives after the deadline
+    // or the external stop.
+    let mut best_result_yet = None;
+    let result = loop {
+        let stopped = external_stop.is_some_and(|external_stop| external_stop.load(Ordering::Relaxed));
+        if stopped && best_result_yet.is_some() {
+            break best_result_yet;
+        }
+        let timeout = match (&best_result_yet, deadline) {
+            (Some(_), Some(deadline)) => Some(deadline.saturating_duration_since(Instant::now())),
+            _                         => None,
+        };
+        let timeout = match external_stop {
+            Some(_) => Some(timeout.map_or(STOP_POLL_INTERVAL, |timeout| timeout.min(STOP_POLL_INTERVAL))),
+            None    => timeout,
+        };
+        let message = match timeout {
+            Some(timeout) => rx.recv_timeout(timeout),
+            None          => rx.recv().map_err(RecvTimeoutError::from),
+        };
         match message {
-            Message::BestYetMove(move_) => {
-                best_move_yet = Some(move_);
+            Ok(Message::BestYetResult(result)) => {
+                if let Some(on_progress) = on_progress {
+                    on_progress(&result);
+                }
+                best_result_yet = Some(result);
+            },
+            Ok(Message::SearchTerminatedResult(result)) => {
+                break Some(result);
             },
-            Message::SearchTerminatedMove(move_) => {
-                return move_;
+            Err(RecvTimeoutError::Timeout) => {
+                let deadline_passed = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                if deadline_passed && best_result_yet.is_some() {
+                    break best_result_yet;
+                }
             },
-            Message::TimeUp => {
-                return best_move_yet.expect("depth 1 search ran out of time");
+            Err(RecvTimeoutError::Disconnected) => {
+                // Stopped by the node limit, or panicked.
+                break best_result_yet;
             },
         }
+    };
+
+    // No search work outlives the returned move.
+    stop.store(true, Ordering::Relaxed);
+    for search_thread in search_threads {
+        if let Err(payload) = search_thread.join() {
+            panic::resume_unwind(payload);
+        }
     }
-    unreachable!();
+
+    result.expect("search thread stopped without a move")
 }
 
+// Lines cut short by transposition table cutoffs are continued with the best
+// moves stored in the table.
+fn extend_principal_variation(
+    board_state:         &BoardState,
+    transposition_table: &TranspositionTable,
+    principal_variation: &mut Vec<Move>,
+    max_length:          usize,
+) {
+    let mut board_state = principal_variation
+        .iter()
+        .fold(*board_state, |board_state, move_| board_state.do_move(*move_));
+
+    while principal_variation.len() < max_length {
+        let (TranspositionTableResponse::PresentHighDepth { best_move: Some(move_), .. } |
+             TranspositionTableResponse::PresentLowDepth  { best_move: Some(move_), .. }) =
+            transposition_table.get(&board_state, 0, principal_variation.len() as u32, EVAL_LOST - 1.0, EVAL_WON + 1.0) else {
+            break;
+        };
+        // Entries may belong to colliding positions, so moves are checked.
+        let Ok(new_board_state) = board_state.try_do_move(move_) else {
+            break;
+        };
+        principal_variation.push(move_);
+        board_state = new_board_state;
+    }
+}
+
+// Returns `None` once the search should stop, without storing anything for unfinished nodes.
+// The line of best moves found below this node is written to `principal_variation`.
 fn minimax_inner (
     board_state:         &BoardState,
-    transposition_table: &mut TranspositionTable,
+    context:             &SearchContext,
     depth:               u32,
-    own_turn:            bool,
+    ply:                 u32, // Distance from the root.
     mut alpha:           Eval,
     mut beta:            Eval,
-) -> Eval {
-    let transposition_table_response = transposition_table.get(board_state, depth);
+    principal_variation: &mut Vec<Move>,
+) -> Option<Eval> {
+    principal_variation.clear();
+    // The root player moves on even plies.
+    let own_turn = ply.is_multiple_of(2);
+    if context.should_stop() {
+        return None;
+    }
+    context.nodes.fetch_add(1, Ordering::Relaxed);
+    let transposition_table = context.transposition_table;
+
+    let transposition_table_response = transposition_table.get(board_state, depth, ply, alpha, beta);
     
-    if let TranspositionTableResponse::PresentHighDepth { eval, .. } = transposition_table_response {
-        return eval;
+    // The root is always searched, as entries from earlier searches would leave no move.
+    if let TranspositionTableResponse::PresentHighDepth { eval, .. } = transposition_table_response && ply > 0 {
+        return Some(eval);
     }
 
-    if depth == 0 || matches!(board_state.state(), PatternState::Won(_)) {
+    if depth == 0 || board_state.state() != PatternState::Undecided {
         let eval = if own_turn {
              eval(board_state)
         } else {
             -eval(board_state)
         };
-        transposition_table.set(board_state, depth, eval, None);
-        return eval;
+        let eval = eval_at_ply(eval, ply);
+        transposition_table.set(board_state, depth, ply, eval, Bound::Exact, None);
+        return Some(eval);
     }
     
     let eligible_moves = board_state.eligible_moves();
@@ -116,42 +343,296 @@ fn minimax_inner (
             }
             sorted_moves.push(*move_);
         }
+    } else {
+        sorted_moves.extend_from_slice(&eligible_moves);
     }
-    sorted_moves.extend_from_slice(&eligible_moves);
 
+    let (original_alpha, original_beta) = (alpha, beta);
     let mut best_eval = if own_turn {
         EVAL_LOST
     } else {
         EVAL_WON
     };
-    let mut best_move = sorted_moves[0]; // Will always be overwritten.
-    for move_ in sorted_moves {
+    let mut best_move = sorted_moves[0];
+    let mut child_principal_variation = Vec::new();
+    for (index, move_) in sorted_moves.into_iter().enumerate() {
         let eval = minimax_inner(
-            &board_state.do_move(move_), transposition_table,
-            depth - 1, !own_turn,
+            &board_state.do_move(move_), context,
+            depth - 1, ply + 1,
             alpha, beta,
-        );
+            &mut child_principal_variation,
+        )?;
+        // The first move is kept even if no move beats the initial best eval.
+        let improved = index == 0 || if own_turn {
+            eval > best_eval
+        } else {
+            eval < best_eval
+        };
+        if improved {
+            principal_variation.clear();
+            principal_variation.push(move_);
+            principal_variation.extend_from_slice(&child_principal_variation);
+        }
         if own_turn {
-            if eval > best_eval {
+            if improved {
                 best_eval = eval;
                 best_move = move_;
             }
             if best_eval >= beta { // Beta cutoff.
-                return best_eval;
+                break;
             }
             alpha = alpha.max(best_eval);
         } else {
-            if eval < best_eval {
+            if improved {
                 best_eval = eval;
                 best_move = move_;
             }
             if best_eval <= alpha { // Alpha cutoff.
-                return best_eval;
+                break;
             }
-            beta = beta.min(eval);
+            beta = beta.min(best_eval);
+        }
+    }
+
+    let bound = Bound::from_window(best_eval, original_alpha, original_beta);
+    transposition_table.set(board_state, depth, ply, best_eval, bound, Some(best_move));
+    Some(best_eval)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+    use crate::{algorithms::{engine::{Engine, Limits}, minimax::{eval::{EVAL_LOST, EVAL_WON, Eval, eval, eval_at_ply}, transposition_table::TranspositionTable}}, utils::{board_state::BoardState, pattern::PatternState, Move, Place, Player, Spot}};
+
+    use super::{minimax_inner, MinimaxEngine, SearchConfig, SearchContext};
+
+    fn plain_minimax(board_state: &BoardState, depth: u32, ply: u32, own_turn: bool) -> Eval {
+        if depth == 0 || board_state.state() != PatternState::Undecided {
+            let eval = if own_turn { eval(board_state) } else { -eval(board_state) };
+            return eval_at_ply(eval, ply);
+        }
+        let evals = board_state
+            .eligible_moves()
+            .iter()
+            .map(|move_| plain_minimax(&board_state.do_move(*move_), depth - 1, ply + 1, !own_turn))
+            .collect::<Vec<_>>();
+        if own_turn {
+            evals.into_iter().fold(EVAL_LOST, Eval::max)
+        } else {
+            evals.into_iter().fold(EVAL_WON,  Eval::min)
+        }
+    }
+
+    #[test]
+    fn matches_plain_minimax() {
+        let mut board_state = BoardState::new_empty(Player::Cross);
+        let moves = [
+            (Place::MidMid, Place::MidMid),
+            (Place::MidMid, Place::TopLef),
+            (Place::TopLef, Place::MidMid),
+            (Place::MidMid, Place::BotRig),
+        ];
+        for (subboard, square) in moves {
+            board_state = board_state.do_move(Move::new(Spot { subboard, square }));
+        }
+
+        // Reusing the table across depths exercises probes of shallower entries.
+        let transposition_table = TranspositionTable::new(1);
+        let stop = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        let context = SearchContext {
+            transposition_table: &transposition_table,
+            stop: &stop,
+            nodes: &nodes,
+            max_nodes: None,
+            interruptible: true,
+        };
+        let mut principal_variation = Vec::new();
+        for depth in 1..=3 {
+            let eval = minimax_inner(
+                &board_state, &context,
+                depth, 0,
+                EVAL_LOST - 1.0, EVAL_WON + 1.0,
+                &mut principal_variation,
+            );
+            assert_eq!(eval, Some(plain_minimax(&board_state, depth, 0, true)));
         }
+
+        // Entries stored for the other player's moves are read from their side.
+        let board_state = board_state.do_move(principal_variation[0]);
+        let eval = minimax_inner(
+            &board_state, &context,
+            2, 0,
+            EVAL_LOST - 1.0, EVAL_WON + 1.0,
+            &mut principal_variation,
+        );
+        assert_eq!(eval, Some(plain_minimax(&board_state, 2, 0, true)));
     }
 
-    transposition_table.set(board_state, depth, best_eval, Some(best_move));
-    best_eval
+    #[test]
+    fn stopped() {
+        let board_state = BoardState::new_empty(Player::Cross);
+        let transposition_table = TranspositionTable::new(1);
+        let stop = AtomicBool::new(true);
+        let nodes = AtomicU64::new(0);
+        let mut context = SearchContext {
+            transposition_table: &transposition_table,
+            stop: &stop,
+            nodes: &nodes,
+            max_nodes: None,
+            interruptible: true,
+        };
+        let mut principal_variation = Vec::new();
+        let eval = minimax_inner(
+            &board_state, &context,
+            3, 0,
+            EVAL_LOST - 1.0, EVAL_WON + 1.0,
+            &mut principal_variation,
+        );
+        assert_eq!(eval, None);
+
+        context.interruptible = false;
+        let eval = minimax_inner(
+            &board_state, &context,
+            1, 0,
+            EVAL_LOST - 1.0, EVAL_WON + 1.0,
+            &mut principal_variation,
+        );
+        assert!(eval.is_some());
+
+        stop.store(false, Ordering::Relaxed);
+        context.interruptible = true;
+        context.max_nodes = Some(nodes.load(Ordering::Relaxed));
+        let eval = minimax_inner(
+            &board_state, &context,
+            2, 0,
+            EVAL_LOST - 1.0, EVAL_WON + 1.0,
+            &mut principal_variation,
+        );
+        assert_eq!(eval, None);
+    }
+
+    #[test]
+    fn minimax_with_config() {
+        let board_state = BoardState::new_empty(Player::Cross);
+        let config = SearchConfig {
+            time_limit: None,
+            max_depth_plies: 2,
+            threads: 2,
+            ..SearchConfig::default()
+        };
+        let result = super::minimax_with_config(&board_state, &config);
+        assert!(board_state.eligible_moves().contains(&result.best_move));
+        assert_eq!(result.depth, 2);
+        assert!(result.nodes > 81);
+
+        let config = SearchConfig {
+            time_limit: None,
+            max_nodes: Some(1),
+            ..SearchConfig::default()
+        };
+        let result = super::minimax_with_config(&board_state, &config);
+        assert!(board_state.eligible_moves().contains(&result.best_move));
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn principal_variation() {
+        let board_state = BoardState::new_empty(Player::Cross);
+        let config = SearchConfig {
+            time_limit: None,
+            max_depth_plies: 4,
+            ..SearchConfig::default()
+        };
+        let result = MinimaxEngine::new(config.clone()).search(&board_state);
+        assert_eq!(result.principal_variation.first(), Some(&result.best_move));
+        assert_eq!(result.principal_variation.len(), 4);
+
+        let end = result.principal_variation
+            .iter()
+            .fold(board_state, |board_state, move_| board_state.do_move(*move_));
+        // After an even number of moves, cross is to move again at the end of the line.
+        assert_eq!(result.eval, eval(&end));
+    }
+
+    #[test]
+    fn win_distance() {
+        // Cross wins by taking the top right subboard, either now or later.
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X  ",
+                "     .     .O O  ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .  O  .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], 2, "cross",
+        );
+        let config = SearchConfig {
+            time_limit: None,
+            max_depth_plies: 5,
+            ..SearchConfig::default()
+        };
+        let result = MinimaxEngine::new(config.clone()).search(&board_state);
+        assert_eq!(result.win_in(), Some(1));
+        assert_eq!(result.depth, 1);
+        assert_eq!(result.best_move, Move::new(Spot { subboard: Place::TopRig, square: Place::TopRig }));
+
+        // Dot can only delay the loss by blocking. Any other move sends cross to a full subboard or
+        // to one with its own threat.
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X  ",
+                "     .     .     ",
+                "     .     .     ",
+
+                "X O X.X    .X O X",
+                "X O O.  X  .X O O",
+                "O X X.    X.O X X",
+
+                "X O X.     .     ",
+                "X O O.     .X    ",
+                "O X X.  X  .    X",
+            ], 2, "dot",
+        );
+        let config = SearchConfig {
+            max_depth_plies: 7,
+            ..config
+        };
+        let block = Move::new(Spot { subboard: Place::TopRig, square: Place::TopRig });
+        let result = MinimaxEngine::new(config.clone()).search(&board_state);
+        assert_eq!(result.loss_in(), Some(6));
+        assert_eq!(result.best_move, block);
+
+        let after_block = MinimaxEngine::new(config.clone()).search(&board_state.do_move(block));
+        assert_eq!(after_block.win_in(), Some(5));
+        let elsewhere = Move::new(Spot { subboard: Place::TopRig, square: Place::MidLef });
+        let after_elsewhere = MinimaxEngine::new(config).search(&board_state.do_move(elsewhere));
+        assert_eq!(after_elsewhere.win_in(), Some(1));
+    }
+
+    #[test]
+    fn engine_repeated_search() {
+        // The second search finds the root in the table from the first.
+        let board_state = BoardState::new_empty(Player::Cross);
+        let limits = Limits {
+            time: None,
+            depth: Some(3),
+            ..Limits::default()
+        };
+        let mut engine = MinimaxEngine::new(SearchConfig {
+            transposition_table_size_mb: 1,
+            ..SearchConfig::default()
+        });
+        let first = engine.choose_move(&board_state, &limits);
+        let second = engine.choose_move(&board_state, &limits);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.eval, second.eval);
+    }
 }
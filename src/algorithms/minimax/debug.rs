@@ -1,4 +1,4 @@
-use crate::{algorithms::minimax::eval::{EVAL_LOST, EVAL_WON, Eval, eval, eval_terms}, utils::{Move, board_state::BoardState}};
+use crate::{algorithms::minimax::eval::{Eval, eval, eval_terms, plies_to_loss, plies_to_win}, utils::{Move, board_state::BoardState}};
 
 #[allow(unused)]
 pub fn dbg_print_moves(board_state: &BoardState) {
@@ -31,9 +31,12 @@ pub fn dbg_print_eval_breakdown(board_state: &BoardState, move_: Move, index: us
     );
 }
 
-fn format_eval(eval: Eval) -> String {
-    if eval == EVAL_WON || eval == EVAL_LOST {
-        return format!(" {:+>5}", eval);
+pub(crate) fn format_eval(eval: Eval) -> String {
+    if let Some(plies) = plies_to_win(eval) {
+        return format!("win in {}", plies);
+    }
+    if let Some(plies) = plies_to_loss(eval) {
+        return format!("loss in {}", plies);
     }
     format!("{:+>6.3}", eval)
 }
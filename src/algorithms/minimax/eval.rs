@@ -4,6 +4,62 @@ pub type Eval = f32;
 
 pub const EVAL_WON:  f32 =  1000.0;
 pub const EVAL_LOST: f32 = -1000.0;
+pub const EVAL_DRAW: f32 =     0.0;
+
+// No game lasts longer than this, so evals within this many plies of
+// `EVAL_WON` or `EVAL_LOST` encode a forced win or loss and its distance.
+pub const MAX_GAME_PLIES: u32 = 81;
+
+pub fn is_win(eval: Eval) -> bool {
+    eval >= EVAL_WON - MAX_GAME_PLIES as Eval
+}
+
+pub fn is_loss(eval: Eval) -> bool {
+    eval <= EVAL_LOST + MAX_GAME_PLIES as Eval
+}
+
+// The number of plies until the game is won, if `eval` is a forced win.
+pub fn plies_to_win(eval: Eval) -> Option<u32> {
+    is_win(eval).then_some((EVAL_WON - eval) as u32)
+}
+
+// The number of plies until the game is lost, if `eval` is a forced loss.
+pub fn plies_to_loss(eval: Eval) -> Option<u32> {
+    is_loss(eval).then_some((eval - EVAL_LOST) as u32)
+}
+
+// Moves a game ending `ply` plies further away, so shorter wins and longer
+// losses are preferred.
+pub fn eval_at_ply(eval: Eval, ply: u32) -> Eval {
+    if is_win(eval) {
+        eval - ply as Eval
+    } else if is_loss(eval) {
+        eval + ply as Eval
+    } else {
+        eval
+    }
+}
+
+// Win and loss distances are stored relative to the stored position rather than
+// to the root, as the same position may be reached at different plies. Evals
+// are stored from the perspective of the player to move there rather than the
+// root player, who is to move on even plies, so entries stay valid in searches
+// from either side.
+pub fn eval_to_table(eval: Eval, ply: u32) -> Eval {
+    let eval = if is_win(eval) {
+        eval + ply as Eval
+    } else if is_loss(eval) {
+        eval - ply as Eval
+    } else {
+        eval
+    };
+    if ply.is_multiple_of(2) { eval } else { -eval }
+}
+
+pub fn eval_from_table(eval: Eval, ply: u32) -> Eval {
+    let eval = if ply.is_multiple_of(2) { eval } else { -eval };
+    eval_at_ply(eval, ply)
+}
 
 pub fn eval(board_state: &BoardState) -> Eval {
     match board_state.state() {
@@ -13,6 +69,9 @@ pub fn eval(board_state: &BoardState) -> Eval {
         PatternState::Won(player) if player != board_state.turn() => {
             return EVAL_LOST;
         },
+        PatternState::Drawn => {
+            return EVAL_DRAW;
+        },
         _ => (),
     }
 
@@ -161,6 +220,7 @@ pub(super) mod eval_terms {
         (places_eval(own_piece_places) - places_eval(opposite_piece_places)) * ACTIVE_SUBBOARD_PIECES_FACTOR
     }
 
+    #[allow(clippy::boxed_local)]
     fn places_eval(places: Box<[Place]>) -> Eval {
         places
             .iter()
@@ -1,15 +1,155 @@
-use std::collections::HashMap;
+use std::{mem, sync::atomic::{AtomicU64, AtomicU8, Ordering}};
 
-use crate::{algorithms::minimax::eval::{EVAL_LOST, EVAL_WON, Eval}, utils::{Move, board_state::BoardState}};
+use crate::{algorithms::minimax::eval::{EVAL_LOST, EVAL_WON, Eval, eval_from_table, eval_to_table}, utils::{Move, Place, Spot, board_state::BoardState}};
+
+// How a stored eval relates to the true eval of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower, // The search failed high; the true eval is at least this.
+    Upper, // The search failed low; the true eval is at most this.
+}
+
+impl Bound {
+    // Fail-soft alpha-beta: evals outside the original window are only bounds.
+    pub fn from_window(eval: Eval, alpha: Eval, beta: Eval) -> Self {
+        if eval <= alpha {
+            Bound::Upper
+        } else if eval >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+
+    // Bounds are stored from the perspective of the player to move, like evals,
+    // so they swap on the plies of the root player's opponent.
+    fn at_ply(self, ply: u32) -> Self {
+        match self {
+            Bound::Lower if !ply.is_multiple_of(2) => Bound::Upper,
+            Bound::Upper if !ply.is_multiple_of(2) => Bound::Lower,
+            bound                                  => bound,
+        }
+    }
+
+    fn to_bits(self) -> u64 {
+        match self {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        }
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        match bits {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct TranspositionEntry {
+    key: u64,
     eval: Eval,
-    depth: u32,
+    bound: Bound,
+    depth: u8,
+    generation: u8,
     is_terminal: bool,
     best_move: Option<Move>,
 }
 
+// Entries are packed into one word of data:
+// * Bits  0..32: eval.
+// * Bits 32..40: depth.
+// * Bits 40..48: generation.
+// * Bits 48..50: bound.
+// * Bit      50: is terminal.
+// * Bits 51..58: best move, as 1 + subboard * 9 + square, or 0 for none.
+// * Bit      58: occupied.
+const OCCUPIED: u64 = 1 << 58;
+
+impl TranspositionEntry {
+    fn pack(&self) -> u64 {
+        let best_move = match self.best_move {
+            Some(move_) => 1 + move_.subboard().to_index() as u64 * 9 + move_.square().to_index() as u64,
+            None => 0,
+        };
+        self.eval.to_bits() as u64 |
+            (self.depth as u64) << 32 |
+            (self.generation as u64) << 40 |
+            self.bound.to_bits() << 48 |
+            (self.is_terminal as u64) << 50 |
+            best_move << 51 |
+            OCCUPIED
+    }
+
+    fn unpack(key: u64, data: u64) -> Self {
+        let best_move = match (data >> 51) & 0x7f {
+            0 => None,
+            index => Some(Move::new(Spot {
+                subboard: Place::from_index((index as usize - 1) / 9),
+                square:   Place::from_index((index as usize - 1) % 9),
+            })),
+        };
+        TranspositionEntry {
+            key,
+            eval: Eval::from_bits(data as u32),
+            depth: (data >> 32) as u8,
+            generation: (data >> 40) as u8,
+            bound: Bound::from_bits((data >> 48) & 0b11),
+            is_terminal: (data >> 50) & 1 == 1,
+            best_move,
+        }
+    }
+}
+
+// The key is stored xored with the data, so an entry torn by concurrent writes
+// fails the key check instead of being read as a corrupt entry.
+#[derive(Debug, Default)]
+struct Slot {
+    checked_key: AtomicU64,
+    data:        AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> Option<TranspositionEntry> {
+        let data = self.data.load(Ordering::Relaxed);
+        if data & OCCUPIED == 0 {
+            return None;
+        }
+        let key = self.checked_key.load(Ordering::Relaxed) ^ data;
+        Some(TranspositionEntry::unpack(key, data))
+    }
+
+    fn store(&self, entry: Option<TranspositionEntry>) {
+        let (key, data) = match entry {
+            Some(entry) => (entry.key, entry.pack()),
+            None => (0, 0),
+        };
+        self.checked_key.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+// Each bucket holds one entry kept by depth and one that is always replaced,
+// so deep results survive while recent shallow results still get stored.
+#[derive(Debug, Default)]
+struct Bucket {
+    depth_preferred: Slot,
+    always_replace:  Slot,
+}
+
+impl Bucket {
+    fn find(&self, key: u64) -> Option<TranspositionEntry> {
+        [self.depth_preferred.load(), self.always_replace.load()]
+            .into_iter()
+            .flatten()
+            .find(|entry| entry.key == key)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TranspositionTableResponse {
     NotPresent,
@@ -23,21 +163,48 @@ pub enum TranspositionTableResponse {
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Shared between search threads without locking.
+#[derive(Debug)]
 pub struct TranspositionTable {
-    table: HashMap<BoardState, TranspositionEntry>,
+    buckets: Box<[Bucket]>, // Length is a power of two.
+    generation: AtomicU8,
 }
 
 impl TranspositionTable {
-    pub fn new() -> Self {
+    pub fn new(size_mb: usize) -> Self {
+        let bucket_count = (size_mb * 1024 * 1024 / mem::size_of::<Bucket>()).max(1);
+        // Round down to a power of two so the key can be masked into an index.
+        let bucket_count = 1 << bucket_count.ilog2();
         Self {
-            table: HashMap::new(),
+            buckets: (0..bucket_count).map(|_| Bucket::default()).collect(),
+            generation: AtomicU8::new(0),
         }
     }
 
-    pub fn get(&self, board_state: &BoardState, depth: u32) -> TranspositionTableResponse {
-        if let Some(entry) = self.table.get(board_state) {
-            if entry.depth >= depth || entry.is_terminal {
+    // Entries from previous searches are kept, but are the first to be replaced.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+
+    // Entries are only returned as usable evals if deep enough and if their bound
+    // decides the eval within the window `alpha`..`beta`. `ply` is the distance of
+    // the position from the root, which win and loss evals are relative to.
+    pub fn get(&self, board_state: &BoardState, depth: u32, ply: u32, alpha: Eval, beta: Eval) -> TranspositionTableResponse {
+        let key = board_state.zobrist_key();
+        if let Some(mut entry) = self.bucket(key).find(key) {
+            entry.eval  = eval_from_table(entry.eval, ply);
+            entry.bound = entry.bound.at_ply(ply);
+            let deep_enough = entry.depth as u32 >= depth || entry.is_terminal;
+            let decides = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.eval >= beta,
+                Bound::Upper => entry.eval <= alpha,
+            };
+            if deep_enough && decides {
                 TranspositionTableResponse::PresentHighDepth {
                     eval: entry.eval,
                     best_move: entry.best_move,
@@ -53,17 +220,173 @@ impl TranspositionTable {
         }
     }
 
-    pub fn set(&mut self, board_state: &BoardState, depth: u32, eval: Eval, best_move: Option<Move>) {
-        if let Some(TranspositionEntry { depth: entry_depth, .. }) = self.table.get(board_state) &&
-            *entry_depth >= depth {
+    pub fn set(&self, board_state: &BoardState, depth: u32, ply: u32, eval: Eval, bound: Bound, best_move: Option<Move>) {
+        let key = board_state.zobrist_key();
+        let eval  = eval_to_table(eval, ply);
+        let bound = bound.at_ply(ply);
+        let depth = depth.min(u8::MAX as u32) as u8;
+        let generation = self.generation.load(Ordering::Relaxed);
+        let bucket = self.bucket(key);
+
+        if let Some(entry) = bucket.find(key) &&
+            entry.depth > depth {
             return;
         }
         let entry = TranspositionEntry {
+            key,
             eval,
+            bound,
             depth,
+            generation,
+            // Only positions where the game has ended are won or lost in zero plies.
             is_terminal: eval == EVAL_WON || eval == EVAL_LOST,
             best_move,
         };
-        self.table.insert(*board_state, entry);
+
+        match bucket.depth_preferred.load() {
+            Some(preferred) if preferred.key != key &&
+                preferred.generation == generation &&
+                preferred.depth > depth => {
+                bucket.always_replace.store(Some(entry));
+            },
+            Some(preferred) if preferred.key != key => {
+                // The replaced entry gets demoted rather than dropped.
+                bucket.always_replace .store(Some(preferred));
+                bucket.depth_preferred.store(Some(entry));
+            },
+            _ => {
+                if bucket.always_replace.load().is_some_and(|entry| entry.key == key) {
+                    bucket.always_replace.store(None);
+                }
+                bucket.depth_preferred.store(Some(entry));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{algorithms::minimax::eval::{EVAL_LOST, EVAL_WON}, utils::{board_state::BoardState, Move, Place, Player, Spot}};
+
+    use super::{Bound, Bucket, TranspositionEntry, TranspositionTable, TranspositionTableResponse};
+
+    // Distinct positions that share a bucket in a single bucket table.
+    fn board_states() -> [BoardState; 3] {
+        let board_state = BoardState::new_empty(Player::Cross);
+        [Place::TopLef, Place::MidMid, Place::BotRig].map(|square| {
+            board_state.do_move(Move::new(Spot {
+                subboard: Place::MidMid,
+                square,
+            }))
+        })
+    }
+
+    #[test]
+    fn size() {
+        let table = TranspositionTable::new(1);
+        assert!(table.buckets.len().is_power_of_two());
+        assert!(table.buckets.len() * size_of::<Bucket>() <= 1024 * 1024);
+        assert!(table.buckets.len() * size_of::<Bucket>() >  512 * 1024);
+        assert_eq!(TranspositionTable::new(0).buckets.len(), 1);
+    }
+
+    #[test]
+    fn pack_unpack() {
+        for (bound, best_move) in [
+            (Bound::Exact, None),
+            (Bound::Lower, Some(Move::new(Spot { subboard: Place::TopLef, square: Place::TopLef }))),
+            (Bound::Upper, Some(Move::new(Spot { subboard: Place::BotRig, square: Place::MidLef }))),
+        ] {
+            let entry = TranspositionEntry {
+                key: 0x0123_4567_89ab_cdef,
+                eval: -12.625,
+                bound,
+                depth: 200,
+                generation: 255,
+                is_terminal: best_move.is_none(),
+                best_move,
+            };
+            assert_eq!(TranspositionEntry::unpack(entry.key, entry.pack()), entry);
+        }
+    }
+
+    #[test]
+    fn get_set() {
+        let [board_state, ..] = board_states();
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.get(&board_state, 1, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+
+        table.set(&board_state, 3, 0, 0.5, Bound::Exact, None);
+        assert!(matches!(table.get(&board_state, 3, 0, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: 0.5, .. }));
+        assert!(matches!(table.get(&board_state, 4, 0, -10.0, 10.0), TranspositionTableResponse::PresentLowDepth  { eval: 0.5, .. }));
+
+        // Shallower results never replace deeper ones.
+        table.set(&board_state, 2, 0, 1.5, Bound::Exact, None);
+        assert!(matches!(table.get(&board_state, 3, 0, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: 0.5, .. }));
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(Bound::from_window(-1.0, -1.0, 1.0), Bound::Upper);
+        assert_eq!(Bound::from_window( 0.0, -1.0, 1.0), Bound::Exact);
+        assert_eq!(Bound::from_window( 1.0, -1.0, 1.0), Bound::Lower);
+
+        let [board_state, ..] = board_states();
+        let table = TranspositionTable::new(1);
+
+        table.set(&board_state, 3, 0, 0.5, Bound::Lower, None);
+        assert!(matches!(table.get(&board_state, 3, 0, -1.0, 0.5), TranspositionTableResponse::PresentHighDepth { eval: 0.5, .. }));
+        assert!(matches!(table.get(&board_state, 3, 0, -1.0, 1.0), TranspositionTableResponse::PresentLowDepth  { eval: 0.5, .. }));
+
+        table.set(&board_state, 3, 0, 0.5, Bound::Upper, None);
+        assert!(matches!(table.get(&board_state, 3, 0,  0.5, 1.0), TranspositionTableResponse::PresentHighDepth { eval: 0.5, .. }));
+        assert!(matches!(table.get(&board_state, 3, 0,  0.0, 1.0), TranspositionTableResponse::PresentLowDepth  { eval: 0.5, .. }));
+
+        // Seen from the other player, an upper bound is a lower bound.
+        assert!(matches!(table.get(&board_state, 3, 1, -1.0, -0.5), TranspositionTableResponse::PresentHighDepth { eval: -0.5, .. }));
+        assert!(matches!(table.get(&board_state, 3, 1, -1.0,  0.0), TranspositionTableResponse::PresentLowDepth  { eval: -0.5, .. }));
+    }
+
+    #[test]
+    fn win_distance() {
+        let [board_state, ..] = board_states();
+        let table = TranspositionTable::new(1);
+
+        // Won 5 plies from a root 3 plies above the position.
+        table.set(&board_state, 3, 3, EVAL_WON - 5.0, Bound::Exact, None);
+        assert!(matches!(table.get(&board_state, 3, 3, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: 995.0, .. }));
+        assert!(matches!(table.get(&board_state, 3, 1, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: 997.0, .. }));
+
+        table.set(&board_state, 4, 2, EVAL_LOST + 4.0, Bound::Exact, None);
+        assert!(matches!(table.get(&board_state, 3, 4, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: -994.0, .. }));
+        // From a root with the other player to move, the loss is a win.
+        assert!(matches!(table.get(&board_state, 3, 5, -10.0, 10.0), TranspositionTableResponse::PresentHighDepth { eval: 993.0, .. }));
+    }
+
+    #[test]
+    fn replacement() {
+        let [first, second, third] = board_states();
+        let table = TranspositionTable::new(0);
+
+        table.set(&first, 5, 0, 1.0, Bound::Exact, None);
+        table.set(&second, 2, 0, 2.0, Bound::Exact, None);
+        // The deep entry stays; the shallow entries take turns in the other slot.
+        table.set(&third, 3, 0, 3.0, Bound::Exact, None);
+        assert_ne!(table.get(&first, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_eq!(table.get(&second, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_ne!(table.get(&third, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+
+        // Deeper entries take the depth preferred slot and demote the old entry.
+        table.set(&second, 6, 0, 2.0, Bound::Exact, None);
+        assert_ne!(table.get(&first, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_ne!(table.get(&second, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_eq!(table.get(&third, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+
+        // Entries from earlier searches lose their claim to the depth preferred slot.
+        table.new_search();
+        table.set(&third, 1, 0, 3.0, Bound::Exact, None);
+        assert_eq!(table.get(&first, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_ne!(table.get(&second, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
+        assert_ne!(table.get(&third, 0, 0, -10.0, 10.0), TranspositionTableResponse::NotPresent);
     }
 }
@@ -0,0 +1,240 @@
+use crate::{algorithms::engine::{Engine, Limits}, client::{Controller, Game}, record::GameRecord, utils::{board_state::BoardState, pattern::PatternState, rng::Rng, Player}};
+
+// Results of a match from the perspective of the first engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchScore {
+    pub wins:   u32,
+    pub draws:  u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn add(&mut self, state: PatternState, player: Player) {
+        match state {
+            PatternState::Won(winner) if winner == player => self.wins   += 1,
+            PatternState::Won(_)                          => self.losses += 1,
+            PatternState::Drawn                           => self.draws  += 1,
+            PatternState::Undecided                       => panic!("invalid result; game not over"),
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // The mean score per game, with draws counting as half a win. None before any game is played.
+    pub fn score(&self) -> Option<f64> {
+        if self.games() == 0 {
+            return None;
+        }
+        Some((self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64)
+    }
+
+    // The variance of the score of a single game.
+    fn variance(&self) -> Option<f64> {
+        let score = self.score()?;
+        let games = self.games() as f64;
+        Some((self.wins   as f64 * (1.0 - score).powi(2) +
+              self.draws  as f64 * (0.5 - score).powi(2) +
+              self.losses as f64 * (0.0 - score).powi(2)) / games)
+    }
+
+    pub fn elo(&self) -> Option<f64> {
+        self.score().map(score_to_elo)
+    }
+
+    // Half the width of the 95% confidence interval of `elo`.
+    pub fn elo_error(&self) -> Option<f64> {
+        let score = self.score()?;
+        let score_error = 1.96 * (self.variance()? / self.games() as f64).sqrt();
+        let lower = score_to_elo((score - score_error).max(0.0));
+        let upper = score_to_elo((score + score_error).min(1.0));
+        // Without both wins and losses the interval is unbounded.
+        if lower.is_infinite() || upper.is_infinite() {
+            return Some(f64::INFINITY);
+        }
+        Some((upper - lower) / 2.0)
+    }
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtVerdict {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+// A sequential probability ratio test of whether the first engine is `elo1`
+// rather than `elo0` stronger, with error rates `alpha` and `beta`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0:  f64,
+    pub elo1:  f64,
+    pub alpha: f64,
+    pub beta:  f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta:  0.05,
+        }
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    // The log-likelihood ratio, approximating game results as normally distributed.
+    pub fn llr(&self, match_score: &MatchScore) -> f64 {
+        let (Some(score), Some(variance)) = (match_score.score(), match_score.variance()) else {
+            return 0.0;
+        };
+        let score0 = elo_to_score(self.elo0);
+        let score1 = elo_to_score(self.elo1);
+        // Identical results have no variance, so the variance of a game between
+        // equal engines without draws is assumed instead.
+        let variance = match variance {
+            0.0      => 0.25,
+            variance => variance,
+        };
+        match_score.games() as f64 * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, match_score: &MatchScore) -> SprtVerdict {
+        let llr = self.llr(match_score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+// Plays up to `plies` random moves from the start, stopping early if the game ends.
+pub fn random_opening(plies: u32, rng: &mut Rng) -> BoardState {
+    let mut board_state = BoardState::new_empty(Player::Cross);
+    for _ in 0..plies {
+        if board_state.state() != PatternState::Undecided {
+            break;
+        }
+        let eligible_moves = board_state.eligible_moves();
+        board_state = board_state.do_move(*rng.choose(&eligible_moves));
+    }
+    board_state
+}
+
+// Plays a game from `board_state` to the end, recording each engine's eval.
+pub fn play_game(
+    cross:       &mut dyn Engine,
+    dot:         &mut dyn Engine,
+    board_state: BoardState,
+    limits:      &Limits,
+) -> GameRecord {
+    let mut game = Game::from_position(board_state);
+    game.set_controller(Player::Cross, Controller::Engine(Box::new(cross)));
+    game.set_controller(Player::Dot, Controller::Engine(Box::new(dot)));
+    game.play_engine_moves(limits);
+
+    let mut record = game.to_record();
+    record.time_control = time_control(limits);
+    record
+}
+
+// The limits in the words of the protocol's `go` command, like "movetime 100 depth 4".
+fn time_control(limits: &Limits) -> Option<String> {
+    let mut words = Vec::new();
+    if let Some(time) = limits.time {
+        words.push(format!("movetime {}", time.as_millis()));
+    }
+    if let Some(depth) = limits.depth {
+        words.push(format!("depth {}", depth));
+    }
+    if let Some(nodes) = limits.nodes {
+        words.push(format!("nodes {}", nodes));
+    }
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{algorithms::{engine::Limits, greedy::GreedyEngine}, utils::{pattern::PatternState, rng::Rng, Player}};
+
+    use super::{play_game, random_opening, MatchScore, Sprt, SprtVerdict};
+
+    #[test]
+    fn elo() {
+        let even = MatchScore { wins: 10, draws: 5, losses: 10 };
+        assert!(even.elo().unwrap().abs() < 1e-9);
+        assert!(even.elo_error().unwrap() > 0.0);
+
+        let winning = MatchScore { wins: 75, draws: 0, losses: 25 };
+        let losing = MatchScore { wins: 25, draws: 0, losses: 75 };
+        assert!((winning.elo().unwrap() - 190.85).abs() < 0.01);
+        assert!((winning.elo().unwrap() + losing.elo().unwrap()).abs() < 1e-9);
+
+        // More games give tighter bounds.
+        let more_games = MatchScore { wins: 750, draws: 0, losses: 250 };
+        assert!(more_games.elo_error().unwrap() < winning.elo_error().unwrap());
+
+        // Nothing can be said before any game is played.
+        let none = MatchScore::default();
+        assert_eq!(none.score(), None);
+        assert_eq!(none.elo(), None);
+        assert_eq!(none.elo_error(), None);
+    }
+
+    #[test]
+    fn add() {
+        let mut match_score = MatchScore::default();
+        match_score.add(PatternState::Won(Player::Cross), Player::Cross);
+        match_score.add(PatternState::Won(Player::Cross), Player::Dot);
+        match_score.add(PatternState::Drawn, Player::Dot);
+        assert_eq!(match_score, MatchScore { wins: 1, draws: 1, losses: 1 });
+        assert_eq!(match_score.games(), 3);
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001);
+        assert!((upper - 2.944).abs() < 0.001);
+
+        assert_eq!(sprt.verdict(&MatchScore { wins: 600, draws: 0, losses: 400 }), SprtVerdict::AcceptH1);
+        assert_eq!(sprt.verdict(&MatchScore { wins: 400, draws: 0, losses: 600 }), SprtVerdict::AcceptH0);
+        assert_eq!(sprt.verdict(&MatchScore { wins: 6, draws: 0, losses: 4 }), SprtVerdict::Continue);
+        assert_eq!(sprt.verdict(&MatchScore::default()), SprtVerdict::Continue);
+        assert_eq!(sprt.verdict(&MatchScore { wins: 0, draws: 0, losses: 300 }), SprtVerdict::AcceptH0);
+    }
+
+    #[test]
+    fn greedy_self_play() {
+        let mut rng = Rng::new(1);
+        let board_state = random_opening(4, &mut rng);
+        assert_eq!(board_state.turn(), Player::Cross);
+
+        let record = play_game(&mut GreedyEngine, &mut GreedyEngine, board_state, &Limits::default());
+        assert_ne!(record.result, PatternState::Undecided);
+        assert_eq!(record.start, board_state);
+        assert_eq!(record.final_state().map(|board_state| board_state.state()), Ok(record.result));
+        assert_eq!(record.time_control.as_deref(), Some("movetime 1000"));
+    }
+}
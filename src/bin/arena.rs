@@ -0,0 +1,172 @@
+use std::{env, fs::File, io::Write, process, time::Duration};
+
+use rustbot::{algorithms::engine::{engine_from_spec, Limits}, arena::{play_game, random_opening, MatchScore, Sprt, SprtVerdict}, record::format_result, utils::{rng::Rng, Player}};
+
+const USAGE: &str = "\
+usage: arena <engine> <engine> [options]
+
+Engines are given as a name with optional options, e.g. \"minimax:depth=6,tt=64\".
+
+options:
+    --games <n>            Number of games to play (default 100).
+    --time <ms>            Time limit per move (default 100).
+    --depth <plies>        Depth limit per move.
+    --nodes <n>            Node limit per move.
+    --opening-plies <n>    Random moves played before the engines take over (default 0).
+    --seed <n>             Seed for the random openings (default 0).
+    --sprt <elo0>,<elo1>   Stop once an SPRT of elo0 against elo1 is decided.
+    --record <path>        Write the games to a file as game records.";
+
+struct Args {
+    engine_specs:  [String; 2],
+    games:         u32,
+    limits:        Limits,
+    opening_plies: u32,
+    seed:          u64,
+    sprt:          Option<Sprt>,
+    record_path:   Option<String>,
+}
+
+fn main() {
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    let mut engines = args.engine_specs.clone().map(|spec| {
+        engine_from_spec(&spec).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        })
+    });
+
+    let mut record_file = args.record_path.as_ref().map(|path| {
+        File::create(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        })
+    });
+
+    let mut rng = Rng::new(args.seed);
+    let mut match_score = MatchScore::default();
+    let mut board_state = random_opening(args.opening_plies, &mut rng);
+    for game in 0..args.games {
+        // Each opening is played twice, with the engines swapping colors.
+        if game % 2 == 0 && game > 0 {
+            board_state = random_opening(args.opening_plies, &mut rng);
+        }
+        let first_player = if game % 2 == 0 { Player::Cross } else { Player::Dot };
+        let [first, second] = &mut engines;
+        let mut record = match first_player {
+            Player::Cross => play_game(first.as_mut(), second.as_mut(), board_state, &args.limits),
+            Player::Dot   => play_game(second.as_mut(), first.as_mut(), board_state, &args.limits),
+        };
+        match_score.add(record.result, first_player);
+
+        if let Some(file) = &mut record_file {
+            let [cross, dot] = match first_player {
+                Player::Cross => [0, 1],
+                Player::Dot   => [1, 0],
+            };
+            record.cross = args.engine_specs[cross].clone();
+            record.dot = args.engine_specs[dot].clone();
+            record.tags.push(("Game".to_owned(), (game + 1).to_string()));
+            if let Err(error) = writeln!(file, "{}", record) {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+
+        let result = format_result(record.result);
+        println!(
+            "game {:>4}: {} as {:<5} {:<7} (+{} ={} -{})",
+            game + 1,
+            args.engine_specs[0],
+            format!("{:?}:", first_player),
+            result,
+            match_score.wins,
+            match_score.draws,
+            match_score.losses,
+        );
+
+        let verdict = args.sprt.map(|sprt| sprt.verdict(&match_score));
+        if verdict.is_some_and(|verdict| verdict != SprtVerdict::Continue) {
+            break;
+        }
+    }
+
+    println!();
+    println!("{} vs {}", args.engine_specs[0], args.engine_specs[1]);
+    println!(
+        "games: {}, wins: {}, draws: {}, losses: {}",
+        match_score.games(),
+        match_score.wins,
+        match_score.draws,
+        match_score.losses,
+    );
+    match (match_score.score(), match_score.elo(), match_score.elo_error()) {
+        (Some(score), Some(elo), Some(elo_error)) => println!("score: {score:.3}, elo: {elo:+.1} +/- {elo_error:.1}"),
+        _                                         => println!("score: no games"),
+    }
+    if let Some(sprt) = args.sprt {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.verdict(&match_score) {
+            SprtVerdict::AcceptH0 => "H0 accepted",
+            SprtVerdict::AcceptH1 => "H1 accepted",
+            SprtVerdict::Continue => "inconclusive",
+        };
+        println!(
+            "sprt elo0: {}, elo1: {}, llr: {:.3} ({:.3}, {:.3}): {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&match_score),
+            lower,
+            upper,
+            verdict,
+        );
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut engine_specs = Vec::new();
+    let mut parsed = Args {
+        engine_specs:  Default::default(),
+        games:         100,
+        limits:        Limits {
+            time: Some(Duration::from_millis(100)),
+            ..Limits::default()
+        },
+        opening_plies: 0,
+        seed:          0,
+        sprt:          None,
+        record_path:   None,
+    };
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            engine_specs.push(arg);
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--games"         => parsed.games = value.parse().map_err(|_| invalid())?,
+            "--time"          => parsed.limits.time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?)),
+            "--depth"         => parsed.limits.depth = Some(value.parse().map_err(|_| invalid())?),
+            "--nodes"         => parsed.limits.nodes = Some(value.parse().map_err(|_| invalid())?),
+            "--opening-plies" => parsed.opening_plies = value.parse().map_err(|_| invalid())?,
+            "--seed"          => parsed.seed = value.parse().map_err(|_| invalid())?,
+            "--record"        => parsed.record_path = Some(value),
+            "--sprt"          => {
+                let (elo0, elo1) = value.split_once(',').ok_or_else(invalid)?;
+                let elo0 = elo0.parse().map_err(|_| invalid())?;
+                let elo1 = elo1.parse().map_err(|_| invalid())?;
+                parsed.sprt = Some(Sprt::new(elo0, elo1));
+            },
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    parsed.engine_specs = engine_specs
+        .try_into()
+        .map_err(|_| "expected two engines".to_owned())?;
+    Ok(parsed)
+}
@@ -0,0 +1,100 @@
+use std::{env, fs::{self, OpenOptions}, io::Write, process, sync::Arc};
+
+use rustbot::host::{play_game, Bot, BotLibrary, Settings};
+
+const USAGE: &str = "\
+usage: host [settings.json] [--seed <n>] [--log <path>] [--record <path>]
+
+Plays one game between the bot libraries named in the settings, without a window.
+The result is printed, and appended to the log file if one is given. The game
+record is appended to the record file if one is given. --seed overrides the
+seed of the settings.
+
+Bots that panic or time out forfeit the game. The bots run inside the host
+process though, so a bot that crashes it, like with a segfault or an abort,
+ends the whole game without a result.";
+
+fn main() {
+    let mut settings_path = "settings.json".to_owned();
+    let mut log_path = None;
+    let mut record_path = None;
+    let mut seed = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--log"    => log_path = Some(args.next().unwrap_or_else(|| exit_with_usage("missing value for --log"))),
+            "--record" => record_path = Some(args.next().unwrap_or_else(|| exit_with_usage("missing value for --record"))),
+            "--seed"   => {
+                let value = args.next().unwrap_or_else(|| exit_with_usage("missing value for --seed"));
+                seed = Some(value.parse::<u64>().unwrap_or_else(|_| exit_with_usage(&format!("invalid value for --seed: {}", value))));
+            },
+            _ if arg.starts_with("--") => exit_with_usage(&format!("unknown option {}", arg)),
+            _ => settings_path = arg,
+        }
+    }
+
+    let settings = fs::read_to_string(&settings_path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| Settings::parse(&text).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| exit_with_error(&format!("{}: {}", settings_path, error)));
+
+    let [cross, dot] = [&settings.cross_path, &settings.dot_path].map(|path| {
+        let library = BotLibrary::open(path).unwrap_or_else(|error| exit_with_error(&error.to_string()));
+        Arc::new(library) as Arc<dyn Bot>
+    });
+
+    let seed = seed.unwrap_or_else(|| settings.seed_or_random());
+    println!("cross: {}", cross.name());
+    println!("dot:   {}", dot.name());
+    println!("seed:  {}", seed);
+
+    let game = play_game(&settings, Arc::clone(&cross), Arc::clone(&dot), seed);
+    for move_ in game.opening_moves() {
+        println!("start: {}", move_);
+    }
+    for (index, move_) in game.bot_moves().enumerate() {
+        println!("{:>3}: {}", index + 1, move_);
+    }
+    println!("{}", game.outcome());
+
+    if let Some(log_path) = log_path {
+        let line = format!(
+            "cross: {}, dot: {}, seed: {}, moves: {}, result: {}\n",
+            cross.name(),
+            dot.name(),
+            seed,
+            game.game.history().len(),
+            game.outcome(),
+        );
+        append(&log_path, &line);
+    }
+    if let Some(record_path) = record_path {
+        let record = game.to_record(&settings, cross.name(), dot.name(), seed);
+        append(&record_path, &format!("{}\n", record));
+    }
+
+    // Bots that timed out may still be running, so the process exits without
+    // waiting for them.
+    process::exit(0);
+}
+
+fn append(path: &str, text: &str) {
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+    if let Err(error) = written {
+        exit_with_error(&format!("{}: {}", path, error));
+    }
+}
+
+fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{}\n\n{}", error, USAGE);
+    process::exit(2);
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    process::exit(1);
+}
@@ -0,0 +1,228 @@
+use std::{env, fs, io::{self, BufRead, IsTerminal, Write}, process, time::Duration};
+
+use rustbot::{algorithms::engine::{engine_from_spec, Engine, Limits, SearchResult}, client::{Controller, Forfeit, ForfeitReason, Game}, utils::{pattern::PatternState, Move, Player, Subboard}};
+
+const USAGE: &str = "\
+usage: play [engine] [options]
+
+Plays a game against an engine in the terminal. The engine is given as a name
+with optional options, e.g. \"minimax:depth=6\" (default minimax).
+
+options:
+    --time <ms>        Time limit per engine move (default 1000).
+    --depth <plies>    Depth limit per engine move.
+    --dot              Play as dot, so the engine moves first.
+    --no-color         Print the board without colours.";
+
+const HELP: &str = "\
+commands:
+    <move>         Play a move, like e5 or 4/4 (subboard/square, 0 to 8 from the top left).
+    undo           Take back your last move and the engine's reply.
+    hint           Ask the engine for a move.
+    swap           Switch sides with the engine.
+    moves          List the legal moves.
+    save <path>    Save the game as a game record.
+    help           Show this help.
+    quit           Leave the game.";
+
+// ANSI escape sequences.
+const RESET:   &str = "\x1b[0m";
+const BOLD:    &str = "\x1b[1m";
+const REVERSE: &str = "\x1b[7m";
+const RED:     &str = "\x1b[31m";
+const GREEN:   &str = "\x1b[32m";
+const YELLOW:  &str = "\x1b[33m";
+const BLUE:    &str = "\x1b[34m";
+
+struct Args {
+    engine_spec: String,
+    limits:      Limits,
+    human:       Player,
+    color:       bool,
+}
+
+fn main() {
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+    let new_engine = || {
+        engine_from_spec(&args.engine_spec).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        })
+    };
+    let mut hint_engine = new_engine();
+
+    let mut game = Game::new();
+    game.set_controller(args.human.opposite(), Controller::Engine(new_engine()));
+    println!("You play {} against {}. Type \"help\" for the commands.", player_name(args.human), args.engine_spec);
+
+    let mut lines = io::stdin().lock().lines();
+    let mut shown = None;
+    loop {
+        while let Some(result) = game.play_engine_move(&args.limits) {
+            // A forfeited move was never played.
+            if game.forfeit().is_none() {
+                println!("{} plays {} ({})", args.engine_spec, result.best_move, describe_eval(&result));
+            }
+        }
+        // The board is only shown again once it or the result changed, as a
+        // forfeit ends the game without a move.
+        if shown != Some((*game.board_state(), game.result())) {
+            shown = Some((*game.board_state(), game.result()));
+            println!();
+            print!("{}", render(&game, args.color));
+            if let Some(Forfeit { player, reason: ForfeitReason::IllegalMove(move_, error) }) = game.forfeit() {
+                println!("{} played the illegal move {} ({}).", player_name(player), move_, error);
+            }
+            match game.result() {
+                PatternState::Won(player) => println!("{} won.", player_name(player)),
+                PatternState::Drawn       => println!("The game is drawn."),
+                PatternState::Undecided   => println!("{} to move.", player_name(game.side_to_move())),
+            }
+        }
+
+        print!("> ");
+        io::stdout().flush().expect("stdout can be flushed");
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None, _)                  => (),
+            (Some("quit"), _)          => break,
+            (Some("help"), _)          => println!("{}", HELP),
+            (Some("undo"), _)          => undo(&mut game),
+            (Some("hint"), _)          => hint(&game, hint_engine.as_mut(), &args.limits),
+            (Some("swap"), _)          => game.swap_controllers(),
+            (Some("moves"), _)         => {
+                let moves: Vec<_> = game.legal_moves().iter().map(Move::to_string).collect();
+                println!("{}", moves.join(" "));
+            },
+            (Some("save"), Some(path)) => save(&game, path),
+            (Some("save"), None)       => println!("Usage: save <path>"),
+            (Some(word), _)            => match word.parse::<Move>() {
+                Ok(move_) => match game.play(move_) {
+                    Ok(())     => (),
+                    Err(error) => println!("Illegal move {}: {}.", move_, error),
+                },
+                Err(_) => println!("Unknown command \"{}\". Type \"help\" for the commands.", word),
+            },
+        }
+    }
+}
+
+// Takes back moves until it is the human's turn again after at least one move.
+fn undo(game: &mut Game) {
+    if game.undo().is_none() {
+        println!("There are no moves to undo.");
+        return;
+    }
+    while matches!(game.controller(game.side_to_move()), Controller::Engine(_)) && game.undo().is_some() {}
+}
+
+fn hint(game: &Game, engine: &mut dyn Engine, limits: &Limits) {
+    if game.is_over() {
+        println!("The game is over.");
+        return;
+    }
+    let result = engine.choose_move(game.board_state(), limits);
+    println!("Hint: {} ({})", result.best_move, describe_eval(&result));
+}
+
+fn save(game: &Game, path: &str) {
+    let mut record = game.to_record();
+    for player in [Player::Cross, Player::Dot] {
+        if let Controller::External = game.controller(player) {
+            match player {
+                Player::Cross => record.cross = "human".to_owned(),
+                Player::Dot   => record.dot = "human".to_owned(),
+            }
+        }
+    }
+    match fs::write(path, record.to_string()) {
+        Ok(())     => println!("Saved the game to {}.", path),
+        Err(error) => println!("Could not save the game to {}: {}.", path, error),
+    }
+}
+
+fn describe_eval(result: &SearchResult) -> String {
+    if let Some(moves) = result.win_in() {
+        format!("wins in {}", moves)
+    } else if let Some(moves) = result.loss_in() {
+        format!("loses in {}", moves)
+    } else {
+        format!("eval {:+.2}", result.eval)
+    }
+}
+
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::Cross => "Cross (X)",
+        Player::Dot   => "Dot (O)",
+    }
+}
+
+// The board as `BoardState::dbg_print` lays it out, with ranks and files for
+// entering moves. Won subboards are coloured by their winner, the places that
+// may be played on are green and the last move is highlighted.
+fn render(game: &Game, color: bool) -> String {
+    let board_state = game.board_state();
+    let last_move = game.history().last().map(|recorded| recorded.move_);
+    let board = board_state.dbg_render(|spot, character| {
+        if !color {
+            return character.to_owned();
+        }
+        let style = match board_state.subboard(spot.subboard) {
+            _ if last_move == Some(Move::new(spot)) => format!("{}{}", BOLD, REVERSE),
+            Subboard::Won(Player::Cross)            => RED.to_owned(),
+            Subboard::Won(Player::Dot)              => BLUE.to_owned(),
+            Subboard::Active(_) if character == "+" => GREEN.to_owned(),
+            _ if character == "X"                   => format!("{}{}", BOLD, RED),
+            _ if character == "O"                   => format!("{}{}", BOLD, BLUE),
+            _                                       => return character.to_owned(),
+        };
+        format!("{}{}{}", style, character, RESET)
+    });
+
+    let mut rendered = String::new();
+    let mut rank = 9;
+    for line in board.lines() {
+        if line.starts_with('-') {
+            rendered.push_str(&format!("  {}\n", line));
+        } else {
+            rendered.push_str(&format!("{} {}\n", rank, line));
+            rank -= 1;
+        }
+    }
+    let files = if color { format!("{}a b c  d e f  g h i{}", YELLOW, RESET) } else { "a b c  d e f  g h i".to_owned() };
+    rendered.push_str(&format!("  {}\n", files));
+    rendered
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        engine_spec: "minimax".to_owned(),
+        limits:      Limits::default(),
+        human:       Player::Cross,
+        color:       io::stdout().is_terminal(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dot"      => parsed.human = Player::Dot,
+            "--no-color" => parsed.color = false,
+            "--time" | "--depth" => {
+                let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+                let invalid = || format!("invalid value for {}: {}", arg, value);
+                match arg.as_str() {
+                    "--time" => parsed.limits.time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?)),
+                    _        => parsed.limits.depth = Some(value.parse().map_err(|_| invalid())?),
+                }
+            },
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => parsed.engine_spec = arg,
+        }
+    }
+    Ok(parsed)
+}
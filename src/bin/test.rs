@@ -1,4 +1,4 @@
-use rustbot::{algorithms::minimax::{debug::dbg_print_moves, minimax}, utils::board_state::BoardState};
+use rustbot::{algorithms::{mcts::{mcts_with_config, MctsConfig}, minimax::{debug::dbg_print_moves, MinimaxEngine}}, utils::board_state::BoardState};
 
 fn main() {
     let board_state = BoardState::dbg_from_matrix(
@@ -21,7 +21,11 @@ fn main() {
     
     dbg_print_moves(&board_state);
 
-    let move_ = minimax(&board_state);
+    let result = MinimaxEngine::default().search(&board_state);
 
-    move_.dbg_print();
+    result.dbg_print();
+
+    let result = mcts_with_config(&board_state, &MctsConfig::default());
+
+    result.dbg_print();
 }
@@ -0,0 +1,17 @@
+use std::{io::{self, BufRead}, sync::{Arc, Mutex}};
+
+use rustbot::protocol::Session;
+
+fn main() {
+    let mut session = Session::new(Arc::new(Mutex::new(io::stdout())));
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !session.handle_line(&line) {
+            return;
+        }
+    }
+    // Input ended without `quit`.
+    session.handle_line("quit");
+}
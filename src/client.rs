@@ -1,14 +1,367 @@
-use crate::utils::{Player, board_state::BoardState};
+use std::fmt;
 
+use crate::{algorithms::engine::{Engine, Limits, SearchResult}, record::{GameRecord, RecordedMove}, utils::{Move, Player, board_state::{BoardState, MoveError}, pattern::PatternState}};
+
+// Who makes the moves of one side.
+pub enum Controller<'a> {
+    External, // Moves are passed to `Game::play`, by a person or a bot library.
+    Engine(Box<dyn Engine + 'a>),
+}
+
+impl fmt::Debug for Controller<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Controller::External       => write!(f, "External"),
+            Controller::Engine(engine) => write!(f, "Engine({})", engine.name()),
+        }
+    }
+}
+
+// Why a side lost the game without it being decided on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForfeitReason {
+    IllegalMove(Move, MoveError),
+    OffBoard(i32, i32), // Subboard and square indices outside the board, as a bot library may answer.
+    Timeout,
+    Crash,
+}
+
+// A side that lost the game by forfeit, like an engine choosing a move that
+// cannot be played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Forfeit {
+    pub player: Player,
+    pub reason: ForfeitReason,
+}
+
+impl fmt::Display for Forfeit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.player {
+            Player::Cross => "cross",
+            Player::Dot   => "dot",
+        };
+        match self.reason {
+            ForfeitReason::IllegalMove(move_, error) => write!(f, "{} forfeited with illegal move {} ({})", name, move_, error),
+            ForfeitReason::OffBoard(sub, spot)       => write!(f, "{} forfeited with move ({}, {}) off the board", name, sub, spot),
+            ForfeitReason::Timeout                   => write!(f, "{} forfeited on time", name),
+            ForfeitReason::Crash                     => write!(f, "{} forfeited by crashing", name),
+        }
+    }
+}
+
+// A game from some start position, with its history and the controllers of
+// both sides. Undone moves can be redone until another move is played.
 #[derive(Debug)]
-pub struct Game {
-    board_state: BoardState,
+pub struct Game<'a> {
+    positions: Vec<BoardState>, // The start position, then one per move in `history`.
+    history:   Vec<RecordedMove>,
+    undone:    Vec<RecordedMove>, // Most recently undone last.
+    forfeit:   Option<Forfeit>, // Ends the game after the last move in `history`.
+    cross:     Controller<'a>,
+    dot:       Controller<'a>,
 }
 
-impl Game {
+impl<'a> Game<'a> {
     pub fn new() -> Self {
+        Self::from_position(BoardState::new_empty(Player::Cross))
+    }
+
+    pub fn from_position(start: BoardState) -> Self {
         Self {
-            board_state: BoardState::new_empty(Player::Cross),
+            positions: vec![start],
+            history:   Vec::new(),
+            undone:    Vec::new(),
+            forfeit:   None,
+            cross:     Controller::External,
+            dot:       Controller::External,
+        }
+    }
+
+    pub fn start(&self) -> &BoardState {
+        &self.positions[0]
+    }
+
+    pub fn board_state(&self) -> &BoardState {
+        &self.positions[self.positions.len() - 1]
+    }
+
+    pub fn side_to_move(&self) -> Player {
+        self.board_state().turn()
+    }
+
+    pub fn legal_moves(&self) -> Box<[Move]> {
+        if self.is_over() {
+            return Box::new([]);
+        }
+        self.board_state().eligible_moves()
+    }
+
+    pub fn result(&self) -> PatternState {
+        match self.forfeit {
+            Some(forfeit) => PatternState::Won(forfeit.player.opposite()),
+            None          => self.board_state().state(),
+        }
+    }
+
+    pub fn forfeit(&self) -> Option<Forfeit> {
+        self.forfeit
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result() != PatternState::Undecided
+    }
+
+    pub fn history(&self) -> &[RecordedMove] {
+        &self.history
+    }
+
+    pub fn controller(&self, player: Player) -> &Controller<'a> {
+        match player {
+            Player::Cross => &self.cross,
+            Player::Dot   => &self.dot,
+        }
+    }
+
+    // Engines are told a new game started when they are handed over.
+    // Returns the previous controller.
+    pub fn set_controller(&mut self, player: Player, mut controller: Controller<'a>) -> Controller<'a> {
+        if let Controller::Engine(engine) = &mut controller {
+            engine.new_game();
+        }
+        match player {
+            Player::Cross => std::mem::replace(&mut self.cross, controller),
+            Player::Dot   => std::mem::replace(&mut self.dot, controller),
+        }
+    }
+
+    pub fn swap_controllers(&mut self) {
+        std::mem::swap(&mut self.cross, &mut self.dot);
+    }
+
+    pub fn play(&mut self, move_: Move) -> Result<(), MoveError> {
+        self.push(RecordedMove { move_, eval: None })
+    }
+
+    fn push(&mut self, recorded: RecordedMove) -> Result<(), MoveError> {
+        if self.forfeit.is_some() {
+            return Err(MoveError::GameOver);
         }
+        let board_state = self.board_state().try_do_move(recorded.move_)?;
+        self.positions.push(board_state);
+        self.history.push(recorded);
+        self.undone.clear();
+        Ok(())
+    }
+
+    // Undoing a forfeit takes back the illegal move, which cannot be redone.
+    // Other forfeits have no move of their own, so the move before them is
+    // taken back with them.
+    pub fn undo(&mut self) -> Option<Move> {
+        if let Some(Forfeit { reason: ForfeitReason::IllegalMove(move_, _), .. }) = self.forfeit.take() {
+            return Some(move_);
+        }
+        let recorded = self.history.pop()?;
+        self.positions.pop();
+        self.undone.push(recorded);
+        Some(recorded.move_)
+    }
+
+    // The side to move loses the game, for a reason found outside the game,
+    // like a bot that does not answer in time.
+    pub fn forfeit_side_to_move(&mut self, reason: ForfeitReason) {
+        if self.is_over() {
+            panic!("invalid forfeit; game is over");
+        }
+        self.forfeit = Some(Forfeit { player: self.side_to_move(), reason });
+    }
+
+    pub fn redo(&mut self) -> Option<Move> {
+        if self.forfeit.is_some() {
+            return None;
+        }
+        let recorded = self.undone.pop()?;
+        // Undone moves were legal in the position they are redone in.
+        let board_state = self.board_state().do_move(recorded.move_);
+        self.positions.push(board_state);
+        self.history.push(recorded);
+        Some(recorded.move_)
+    }
+
+    // Lets the engine of the side to move make its move. Nothing happens when
+    // the game is over or the side to move is external. An illegal move forfeits
+    // the game, so engines from untrusted libraries cannot stop it.
+    pub fn play_engine_move(&mut self, limits: &Limits) -> Option<SearchResult> {
+        if self.is_over() {
+            return None;
+        }
+        let board_state = *self.board_state();
+        let controller = match board_state.turn() {
+            Player::Cross => &mut self.cross,
+            Player::Dot   => &mut self.dot,
+        };
+        let Controller::Engine(engine) = controller else {
+            return None;
+        };
+        let result = engine.choose_move(&board_state, limits);
+        if let Err(error) = self.push(RecordedMove { move_: result.best_move, eval: Some(result.eval) }) {
+            self.forfeit_side_to_move(ForfeitReason::IllegalMove(result.best_move, error));
+        }
+        Some(result)
+    }
+
+    // Lets the engines play until the game is over or an external side is to move.
+    pub fn play_engine_moves(&mut self, limits: &Limits) {
+        while self.play_engine_move(limits).is_some() {}
+    }
+
+    // Sides are named after their engines, and external sides are unnamed.
+    // Forfeits are explained in the "Termination" tag.
+    pub fn to_record(&self) -> GameRecord {
+        let mut record = GameRecord::new(*self.start());
+        for player in [Player::Cross, Player::Dot] {
+            let Controller::Engine(engine) = self.controller(player) else {
+                continue;
+            };
+            let (name, engine_name) = match player {
+                Player::Cross => (&mut record.cross, &mut record.cross_engine),
+                Player::Dot   => (&mut record.dot, &mut record.dot_engine),
+            };
+            *name = engine.name().to_owned();
+            *engine_name = Some(engine.name().to_owned());
+        }
+        record.result = self.result();
+        if let Some(forfeit) = self.forfeit {
+            record.tags.push(("Termination".to_owned(), forfeit.to_string()));
+        }
+        record.moves = self.history.clone();
+        record
+    }
+}
+
+impl Default for Game<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{algorithms::{engine::{Engine, Limits, SearchResult}, greedy::GreedyEngine}, utils::{Move, Player, board_state::{BoardState, MoveError}, pattern::PatternState}};
+
+    use super::{Controller, Forfeit, ForfeitReason, Game};
+
+    // Always plays the center square, legal or not.
+    struct CenterEngine;
+
+    impl Engine for CenterEngine {
+        fn name(&self) -> &str {
+            "center"
+        }
+
+        fn choose_move(&mut self, _board_state: &BoardState, _limits: &Limits) -> SearchResult {
+            let move_ = Move::from_global_index(40);
+            SearchResult {
+                best_move: move_,
+                eval: 0.0,
+                depth: 1,
+                nodes: 1,
+                elapsed: Duration::ZERO,
+                principal_variation: vec![move_],
+            }
+        }
+    }
+
+    #[test]
+    fn play_undo_redo() {
+        let mut game = Game::new();
+        let first = Move::from_global_index(40);
+        let second = Move::from_global_index(30);
+        assert_eq!(game.side_to_move(), Player::Cross);
+        assert_eq!(game.legal_moves().len(), 81);
+
+        game.play(first).unwrap();
+        assert_eq!(game.play(first), Err(MoveError::SquareOccupied));
+        game.play(second).unwrap();
+        assert_eq!(game.side_to_move(), Player::Cross);
+        assert_eq!(game.legal_moves().len(), 9);
+
+        assert_eq!(game.undo(), Some(second));
+        assert_eq!(game.undo(), Some(first));
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.board_state(), game.start());
+        assert_eq!(game.redo(), Some(first));
+        assert_eq!(game.redo(), Some(second));
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.history().len(), 2);
+
+        // Playing a move forgets the undone ones.
+        game.undo();
+        game.play(Move::from_global_index(31)).unwrap();
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn engines() {
+        let mut cross = GreedyEngine;
+        let mut game = Game::new();
+        game.set_controller(Player::Cross, Controller::Engine(Box::new(&mut cross)));
+        let limits = Limits::default();
+
+        // The engine waits for the external side.
+        assert!(game.play_engine_move(&limits).is_some());
+        game.play_engine_moves(&limits);
+        assert_eq!(game.history().len(), 1);
+        assert_eq!(game.side_to_move(), Player::Dot);
+
+        game.set_controller(Player::Dot, Controller::Engine(Box::new(GreedyEngine)));
+        game.play_engine_moves(&limits);
+        assert!(game.is_over());
+        assert_eq!(game.legal_moves().len(), 0);
+        assert!(game.play_engine_move(&limits).is_none());
+
+        let record = game.to_record();
+        assert_eq!(record.cross, "greedy");
+        assert_ne!(record.result, PatternState::Undecided);
+        assert_eq!(record.final_state().map(|board_state| board_state.state()), Ok(game.result()));
+
+        game.swap_controllers();
+        assert!(matches!(game.controller(Player::Cross), Controller::Engine(_)));
+    }
+
+    #[test]
+    fn illegal_engine_move() {
+        let mut game = Game::new();
+        game.set_controller(Player::Cross, Controller::Engine(Box::new(CenterEngine)));
+        game.set_controller(Player::Dot, Controller::Engine(Box::new(CenterEngine)));
+        game.play_engine_moves(&Limits::default());
+
+        // Dot is sent to the center subboard, whose center is taken.
+        let forfeit = Forfeit {
+            player: Player::Dot,
+            reason: ForfeitReason::IllegalMove(Move::from_global_index(40), MoveError::SquareOccupied),
+        };
+        assert_eq!(game.forfeit(), Some(forfeit));
+        assert_eq!(game.result(), PatternState::Won(Player::Cross));
+        assert!(game.is_over());
+        assert_eq!(game.history().len(), 1);
+        assert_eq!(game.play(Move::from_global_index(30)), Err(MoveError::GameOver));
+
+        let record = game.to_record();
+        assert_eq!(record.result, PatternState::Won(Player::Cross));
+        assert!(record.tags.contains(&("Termination".to_owned(), forfeit.to_string())));
+
+        assert_eq!(game.undo(), Some(Move::from_global_index(40)));
+        assert_eq!(game.result(), PatternState::Undecided);
+        assert_eq!(game.history().len(), 1);
+
+        // A timeout is taken back along with the move before it.
+        game.forfeit_side_to_move(ForfeitReason::Timeout);
+        assert_eq!(game.result(), PatternState::Won(Player::Cross));
+        assert_eq!(game.forfeit().map(|forfeit| forfeit.to_string()), Some("dot forfeited on time".to_owned()));
+        assert_eq!(game.undo(), Some(Move::from_global_index(40)));
+        assert_eq!(game.result(), PatternState::Undecided);
+        assert_eq!(game.history().len(), 0);
     }
 }
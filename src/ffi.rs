@@ -0,0 +1,594 @@
+use std::{any::Any, ffi::{c_char, c_int, CStr}, panic::{self, AssertUnwindSafe}, ptr, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, OnceLock}, thread::{self, JoinHandle}, time::Duration};
+
+use crate::{algorithms::{engine::{engine_from_spec, Engine, EngineSpecError, Limits}, greedy::greedy}, utils::{board_state::BoardState, pattern::PatternState, FfiBoardState, Move, Place, RawMove, Spot}};
+
+use self::config::log;
+
+pub mod config;
+pub use self::config::{Config, ConfigError};
+
+// The version of the exported functions and structs, declared as
+// `RUSTBOT_ABI_VERSION` in `interface/rustbot.h`. Raise it with any change to them.
+pub const ABI_VERSION: u32 = 1;
+
+#[unsafe(no_mangle)]
+extern "C" fn rustbot_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+// The board state arrives as plain integers and is validated, as a value out
+// of range in a Rust enum would be undefined behaviour. Unwinding out of an
+// `extern "C"` function aborts the host, which would lose the match, so panics
+// are caught instead. Either way a fallback move is played.
+//
+// The configured engine is kept between calls, like a handle from
+// `rustbot_engine_new`, so its transposition table carries over between moves.
+#[unsafe(no_mangle)]
+extern "C" fn get_move(ffi_board_state: FfiBoardState) -> RawMove {
+    static HANDLE: Mutex<Option<EngineHandle>> = Mutex::new(None);
+
+    // Reading the config and creating the handle are guarded too, as they run
+    // on the first call.
+    let played = panic::catch_unwind(|| {
+        let config = config();
+        if config.log_path.is_some()
+            && let Ok(board_state) = BoardState::from_ffi(ffi_board_state)
+        {
+            log(&format!("Turn: {:?}\n{}", board_state.turn(), board_state.dbg_render(|_, character| character.to_owned())));
+        }
+        // Searches catch their panics, so the lock is only poisoned by a bug
+        // here, which is no reason to stop playing.
+        let mut handle = HANDLE.lock().unwrap_or_else(|error| error.into_inner());
+        handle
+            .get_or_insert_with(|| EngineHandle::with_config(config))
+            .get_move(ffi_board_state)
+    });
+    played.unwrap_or_else(|payload| {
+        log(&format!("rustbot: get_move panicked ({}), playing a fallback move", panic_message(&payload)));
+        let move_ = match BoardState::from_ffi(ffi_board_state) {
+            Ok(board_state) => fallback_move(board_state, ffi_board_state),
+            Err(_)          => first_empty_square(ffi_board_state).unwrap_or(Move::from_global_index(0)),
+        };
+        move_.to_raw()
+    })
+}
+
+// Read once, on the first call into the library.
+fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(Config::load)
+}
+
+// A panic in `choose` may leave what it borrows inconsistent, so callers
+// should discard such state when `choose` did not return.
+fn get_move_with(ffi_board_state: FfiBoardState, choose: impl FnOnce(&BoardState) -> Move) -> RawMove {
+    let board_state = match BoardState::from_ffi(ffi_board_state) {
+        Ok(board_state) => board_state,
+        Err(error) => {
+            log(&format!("rustbot: invalid board state ({}), playing the first empty square", error));
+            return first_empty_square(ffi_board_state).unwrap_or(Move::from_global_index(0)).to_raw();
+        },
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| choose(&board_state))) {
+        Ok(move_) => move_.to_raw(),
+        Err(payload) => {
+            log(&format!("rustbot: get_move panicked ({}), playing a fallback move", panic_message(&payload)));
+            fallback_move(board_state, ffi_board_state).to_raw()
+        },
+    }
+}
+
+// Tries greedy, then the first eligible move, then the first empty square of
+// the active subboards, each of which relies on less of the crate.
+fn fallback_move(board_state: BoardState, ffi_board_state: FfiBoardState) -> Move {
+    if let Ok(move_) = panic::catch_unwind(|| greedy(&board_state)) {
+        return move_;
+    }
+    log("rustbot: greedy panicked too, playing the first eligible move");
+    if let Ok(Some(move_)) = panic::catch_unwind(|| board_state.eligible_moves().first().copied()) {
+        return move_;
+    }
+
+    log("rustbot: no eligible move, playing the first empty square");
+    first_empty_square(ffi_board_state).unwrap_or(Move::from_global_index(0))
+}
+
+fn first_empty_square(ffi_board_state: FfiBoardState) -> Option<Move> {
+    let active = |subboard: usize| ffi_board_state.current == -1 || ffi_board_state.current == subboard as i32;
+    (0..9)
+        .filter(|&subboard| active(subboard))
+        .flat_map(|subboard| (0..9).map(move |square| (subboard, square)))
+        .find(|&(subboard, square)| ffi_board_state.board[subboard][square] == 0)
+        .map(|(subboard, square)| Move::new(Spot {
+            subboard: Place::from_index(subboard),
+            square:   Place::from_index(square),
+        }))
+}
+
+// A search on the position after the predicted reply, run on the opponent's
+// time to fill the transposition table.
+struct Ponder {
+    stop:   Arc<AtomicBool>,
+    thread: JoinHandle<Box<dyn Engine>>,
+}
+
+// The state kept between moves by the `rustbot_engine_*` functions.
+//
+// Options, set with `rustbot_engine_set_option`:
+// * `engine`: greedy, minimax or mcts, dropping the options of the old engine.
+// * `time`, `depth` and `nodes`: the limits of each search, in milliseconds,
+//   plies and nodes, with "none" for no limit.
+// * `ponder`: "true" to search on the opponent's time.
+// * Anything else is an option of the engine, as in `engine_from_spec`, like
+//   `tt` or `threads` for minimax.
+pub struct EngineHandle {
+    engine_name:    String,
+    engine_options: Vec<(String, String)>,
+    limits:         Limits,
+    ponder:         bool,
+    engine:         Option<Box<dyn Engine>>, // Lent out while pondering, and dropped when a search panics.
+    pondering:      Option<Ponder>,
+    pieces:         usize, // On the board at the last move, to notice new games.
+}
+
+impl EngineHandle {
+    pub fn new() -> Self {
+        Self::with_config(&Config::default())
+    }
+
+    // Starts from the engine and time limit of `config`.
+    pub fn with_config(config: &Config) -> Self {
+        let spec = config.engine_spec();
+        let (engine_name, engine_options) = match spec.split_once(':') {
+            Some((name, options)) => {
+                let options = options
+                    .split(',')
+                    .map(|option| match option.split_once('=') {
+                        Some((name, value)) => (name.to_owned(), value.to_owned()),
+                        None                => (option.to_owned(), String::new()),
+                    })
+                    .collect();
+                (name.to_owned(), options)
+            },
+            None => (spec, Vec::new()),
+        };
+        Self {
+            engine_name,
+            engine_options,
+            limits:         config.limits(),
+            ponder:         false,
+            engine:         None,
+            pondering:      None,
+            pieces:         0,
+        }
+    }
+
+    fn spec(&self) -> String {
+        let options: Vec<_> = self.engine_options
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        if options.is_empty() {
+            self.engine_name.clone()
+        } else {
+            format!("{}:{}", self.engine_name, options.join(","))
+        }
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineSpecError> {
+        self.stop_pondering();
+        let invalid = || EngineSpecError::InvalidValue(value.to_owned());
+        let limit = |value: &str| match value {
+            "none" => Ok(None),
+            _      => value.parse().map(Some).map_err(|_| invalid()),
+        };
+        match name {
+            "time"   => self.limits.time = limit(value)?.map(Duration::from_millis),
+            "depth"  => self.limits.depth = limit(value)?.map(u32::try_from).transpose().map_err(|_| invalid())?,
+            "nodes"  => self.limits.nodes = limit(value)?,
+            "ponder" => self.ponder = value.parse().map_err(|_| invalid())?,
+            "engine" => {
+                let engine = engine_from_spec(value)?;
+                self.engine_name = value.to_owned();
+                self.engine_options.clear();
+                self.engine = Some(engine);
+            },
+            _ => {
+                let mut engine_options = self.engine_options.clone();
+                engine_options.retain(|(option, _)| option != name);
+                engine_options.push((name.to_owned(), value.to_owned()));
+                let old_options = std::mem::replace(&mut self.engine_options, engine_options);
+                match engine_from_spec(&self.spec()) {
+                    Ok(engine) => self.engine = Some(engine),
+                    Err(error) => {
+                        self.engine_options = old_options;
+                        return Err(error);
+                    },
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // All of the work happens under the panic guard of `get_move_with`. A panic
+    // leaves the engine taken, so the next move is made by a fresh one.
+    pub fn get_move(&mut self, ffi_board_state: FfiBoardState) -> RawMove {
+        get_move_with(ffi_board_state, |board_state| self.choose_move(board_state, ffi_board_state))
+    }
+
+    fn choose_move(&mut self, board_state: &BoardState, ffi_board_state: FfiBoardState) -> Move {
+        self.stop_pondering();
+
+        // Fewer pieces than last time means the host started another game.
+        let pieces = ffi_board_state.board.iter().flatten().filter(|&&piece| piece != 0).count();
+        let new_game = pieces < self.pieces;
+        self.pieces = pieces;
+
+        let mut engine = match self.engine.take() {
+            Some(engine) => engine,
+            None => engine_from_spec(&self.spec()).expect("options are checked when set"),
+        };
+        if new_game {
+            engine.new_game();
+        }
+        let result = engine.choose_move(board_state, &self.limits);
+        self.engine = Some(engine);
+
+        // The engine's line is not trusted to be legal.
+        if self.ponder
+            && let [move_, reply, ..] = result.principal_variation[..]
+            && let Ok(predicted) = board_state.try_do_move(move_)
+            && let Ok(predicted) = predicted.try_do_move(reply)
+        {
+            self.start_pondering(predicted);
+        }
+        result.best_move
+    }
+
+    fn start_pondering(&mut self, board_state: BoardState) {
+        if board_state.state() != PatternState::Undecided {
+            return;
+        }
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let limits = Limits {
+            time:        None,
+            depth:       None,
+            nodes:       None,
+            stop:        Some(Arc::clone(&stop)),
+            on_progress: None,
+        };
+        let thread = thread::spawn(move || {
+            engine.choose_move(&board_state, &limits);
+            engine
+        });
+        self.pondering = Some(Ponder { stop, thread });
+    }
+
+    fn stop_pondering(&mut self) {
+        if let Some(pondering) = self.pondering.take() {
+            pondering.stop.store(true, Ordering::Relaxed);
+            match pondering.thread.join() {
+                Ok(engine) => self.engine = Some(engine),
+                Err(payload) => log(&format!("rustbot: pondering panicked ({})", panic_message(&payload))),
+            }
+        }
+    }
+}
+
+impl Default for EngineHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for EngineHandle {
+    // The ponder thread must not outlive the library it runs in.
+    fn drop(&mut self) {
+        self.stop_pondering();
+    }
+}
+
+// Returns null if the handle could not be created.
+#[unsafe(no_mangle)]
+extern "C" fn rustbot_engine_new() -> *mut EngineHandle {
+    match panic::catch_unwind(|| EngineHandle::with_config(config())) {
+        Ok(handle) => Box::into_raw(Box::new(handle)),
+        Err(payload) => {
+            log(&format!("rustbot: rustbot_engine_new panicked ({})", panic_message(&payload)));
+            ptr::null_mut()
+        },
+    }
+}
+
+// Returns 0 once the option is set, and -1 for unknown options, invalid values
+// and null arguments.
+//
+// # Safety
+// `handle` must come from `rustbot_engine_new` and not be freed, and `name`
+// and `value` must be null or point to nul-terminated strings.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn rustbot_engine_set_option(handle: *mut EngineHandle, name: *const c_char, value: *const c_char) -> c_int {
+    if handle.is_null() || name.is_null() || value.is_null() {
+        return -1;
+    }
+    let (handle, name, value) = unsafe { (&mut *handle, CStr::from_ptr(name), CStr::from_ptr(value)) };
+    let (Ok(name), Ok(value)) = (name.to_str(), value.to_str()) else {
+        return -1;
+    };
+    let set = panic::catch_unwind(AssertUnwindSafe(|| handle.set_option(name, value)));
+    match set {
+        Ok(Ok(()))     => 0,
+        Ok(Err(error)) => {
+            log(&format!("rustbot: cannot set option {}: {}", name, error));
+            -1
+        },
+        Err(payload) => {
+            log(&format!("rustbot: rustbot_engine_set_option panicked ({})", panic_message(&payload)));
+            -1
+        },
+    }
+}
+
+// Like `get_move`, but keeping the engine's state between moves.
+//
+// # Safety
+// `handle` must come from `rustbot_engine_new` and not be freed.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn rustbot_engine_get_move(handle: *mut EngineHandle, ffi_board_state: FfiBoardState) -> RawMove {
+    if handle.is_null() {
+        log("rustbot: rustbot_engine_get_move called without a handle");
+        return get_move_with(ffi_board_state, greedy);
+    }
+    let handle = unsafe { &mut *handle };
+    handle.get_move(ffi_board_state)
+}
+
+// Stops pondering and frees the handle. This must happen before the library
+// is unloaded.
+//
+// # Safety
+// `handle` must be null or come from `rustbot_engine_new` and not be freed.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn rustbot_engine_free(handle: *mut EngineHandle) {
+    if handle.is_null() {
+        return;
+    }
+    let handle = unsafe { Box::from_raw(handle) };
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| drop(handle))) {
+        log(&format!("rustbot: rustbot_engine_free panicked ({})", panic_message(&payload)));
+    }
+}
+
+pub(crate) fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CString, mem::{align_of, offset_of, size_of}, time::Duration};
+
+    use crate::{algorithms::engine::{Engine, EngineSpecError, Limits, SearchResult}, utils::{board_state::BoardState, FfiBoardState, Move, Place, Player, RawBoardState, RawMove, Spot}};
+
+    use super::{get_move, get_move_with, rustbot_abi_version, ABI_VERSION, rustbot_engine_free, rustbot_engine_get_move, rustbot_engine_new, rustbot_engine_set_option, Config, EngineHandle};
+
+    #[test]
+    fn panic_falls_back() {
+        let board_state = BoardState::new_empty(Player::Cross).do_move(Move::from_global_index(40));
+        let ffi_board_state = FfiBoardState::from(board_state.to_raw());
+        let raw_move = get_move_with(ffi_board_state, |_| panic!("invalid test"));
+        let move_ = board_state
+            .eligible_moves()
+            .iter()
+            .copied()
+            .find(|move_| move_.to_raw() == raw_move);
+        assert!(move_.is_some());
+
+        let raw_move = get_move_with(ffi_board_state, |_| Move::from_global_index(30));
+        assert_eq!(raw_move, Move::from_global_index(30).to_raw());
+
+        // Invalid board states are not searched.
+        let mut invalid = ffi_board_state;
+        invalid.turn = 7;
+        let raw_move = get_move_with(invalid, |_| panic!("invalid test"));
+        assert_eq!(raw_move, Move::new(Spot { subboard: Place::MidMid, square: Place::TopLef }).to_raw());
+    }
+
+    #[test]
+    fn first_empty_square() {
+        let mut ffi_board_state = FfiBoardState::from(BoardState::new_empty(Player::Cross).to_raw());
+        assert_eq!(super::first_empty_square(ffi_board_state), Some(Move::from_global_index(0)));
+
+        ffi_board_state.current = 4;
+        ffi_board_state.board[4] = [1, 1, 1, 1, 1, 1, 1, 0, 1];
+        let move_ = Move::new(Spot { subboard: Place::MidMid, square: Place::BotMid });
+        assert_eq!(super::first_empty_square(ffi_board_state), Some(move_));
+
+        ffi_board_state.board[4][7] = -1;
+        assert_eq!(super::first_empty_square(ffi_board_state), None);
+
+        // Out of range values are never indexed with.
+        ffi_board_state.current = 12;
+        assert_eq!(super::first_empty_square(ffi_board_state), None);
+    }
+
+    fn is_eligible(board_state: &BoardState, raw_move: crate::utils::RawMove) -> bool {
+        board_state.eligible_moves().iter().any(|move_| move_.to_raw() == raw_move)
+    }
+
+    #[test]
+    fn handle_options() {
+        let mut handle = EngineHandle::new();
+        assert_eq!(handle.set_option("depth", "2"), Ok(()));
+        assert_eq!(handle.set_option("time", "none"), Ok(()));
+        assert_eq!(handle.set_option("tt", "1"), Ok(()));
+        assert_eq!(handle.spec(), "minimax:tt=1");
+        assert_eq!(handle.set_option("colour", "red"), Err(EngineSpecError::UnknownOption("colour".to_owned())));
+        assert_eq!(handle.spec(), "minimax:tt=1");
+        assert_eq!(handle.set_option("depth", "deep"), Err(EngineSpecError::InvalidValue("deep".to_owned())));
+        assert_eq!(handle.set_option("depth", "4294967296"), Err(EngineSpecError::InvalidValue("4294967296".to_owned())));
+        assert_eq!(handle.limits.depth, Some(2));
+        assert_eq!(handle.set_option("ponder", "yes"), Err(EngineSpecError::InvalidValue("yes".to_owned())));
+        assert_eq!(handle.set_option("engine", "random"), Err(EngineSpecError::UnknownEngine("random".to_owned())));
+        assert_eq!(handle.set_option("engine", "mcts"), Ok(()));
+        assert_eq!(handle.spec(), "mcts");
+
+        let config = Config { engine: "minimax:depth=4".to_owned(), tt_mb: Some(2), ..Config::default() };
+        let handle = EngineHandle::with_config(&config);
+        assert_eq!(handle.spec(), "minimax:depth=4,tt=2");
+        assert_eq!(handle.limits.time, Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn handle_ponders() {
+        let mut handle = EngineHandle::new();
+        handle.set_option("depth", "3").unwrap();
+        handle.set_option("tt", "1").unwrap();
+        handle.set_option("ponder", "true").unwrap();
+
+        let mut board_state = BoardState::new_empty(Player::Cross);
+        for _ in 0..3 {
+            let raw_move = handle.get_move(FfiBoardState::from(board_state.to_raw()));
+            assert!(is_eligible(&board_state, raw_move));
+            assert!(handle.pondering.is_some());
+            let move_ = *board_state.eligible_moves().iter().find(|move_| move_.to_raw() == raw_move).unwrap();
+            board_state = board_state.do_move(move_);
+            board_state = board_state.do_move(board_state.eligible_moves()[0]);
+        }
+        drop(handle);
+    }
+
+    // Plays the first eligible move, claiming to expect the same move as the reply.
+    struct RepeatingEngine;
+
+    impl Engine for RepeatingEngine {
+        fn name(&self) -> &str {
+            "repeating"
+        }
+
+        fn choose_move(&mut self, board_state: &BoardState, _limits: &Limits) -> SearchResult {
+            let move_ = board_state.eligible_moves()[0];
+            SearchResult {
+                best_move: move_,
+                eval: 0.0,
+                depth: 1,
+                nodes: 1,
+                elapsed: Duration::ZERO,
+                principal_variation: vec![move_, move_],
+            }
+        }
+    }
+
+    struct PanickingEngine;
+
+    impl Engine for PanickingEngine {
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        fn choose_move(&mut self, _board_state: &BoardState, _limits: &Limits) -> SearchResult {
+            panic!("invalid test");
+        }
+    }
+
+    #[test]
+    fn handle_survives_engines() {
+        let mut handle = EngineHandle::new();
+        handle.set_option("depth", "1").unwrap();
+        handle.set_option("ponder", "true").unwrap();
+        let board_state = BoardState::new_empty(Player::Cross);
+        let ffi_board_state = FfiBoardState::from(board_state.to_raw());
+
+        // An illegal line is not pondered on.
+        handle.engine = Some(Box::new(RepeatingEngine));
+        assert!(is_eligible(&board_state, handle.get_move(ffi_board_state)));
+        assert!(handle.pondering.is_none());
+
+        // A panicking engine is replaced for the next move.
+        handle.engine = Some(Box::new(PanickingEngine));
+        assert!(is_eligible(&board_state, handle.get_move(ffi_board_state)));
+        assert!(handle.engine.is_none());
+        assert!(is_eligible(&board_state, handle.get_move(ffi_board_state)));
+        assert!(handle.engine.is_some() || handle.pondering.is_some());
+    }
+
+    #[test]
+    fn handle_c_api() {
+        let handle = rustbot_engine_new();
+        assert!(!handle.is_null());
+        let set_option = |name: &str, value: &str| {
+            let (name, value) = (CString::new(name).unwrap(), CString::new(value).unwrap());
+            unsafe { rustbot_engine_set_option(handle, name.as_ptr(), value.as_ptr()) }
+        };
+        assert_eq!(set_option("engine", "greedy"), 0);
+        assert_eq!(set_option("threads", "2"), -1);
+        assert_eq!(unsafe { rustbot_engine_set_option(handle, std::ptr::null(), std::ptr::null()) }, -1);
+
+        let board_state = BoardState::new_empty(Player::Dot).do_move(Move::from_global_index(0));
+        let raw_move = unsafe { rustbot_engine_get_move(handle, FfiBoardState::from(board_state.to_raw())) };
+        assert!(is_eligible(&board_state, raw_move));
+        unsafe { rustbot_engine_free(handle) };
+        unsafe { rustbot_engine_free(std::ptr::null_mut()) };
+    }
+
+    // The structs must match `rustbot_board_state` and `rustbot_move` in the header.
+    #[test]
+    fn layouts() {
+        for (size, align) in [
+            (size_of::<FfiBoardState>(), align_of::<FfiBoardState>()),
+            (size_of::<RawBoardState>(), align_of::<RawBoardState>()),
+        ] {
+            assert_eq!(size, 83 * 4);
+            assert_eq!(align, 4);
+        }
+        assert_eq!(offset_of!(FfiBoardState, board), 0);
+        assert_eq!(offset_of!(FfiBoardState, turn), 81 * 4);
+        assert_eq!(offset_of!(FfiBoardState, current), 82 * 4);
+        assert_eq!(offset_of!(RawBoardState, board), 0);
+        assert_eq!(offset_of!(RawBoardState, turn), 81 * 4);
+        assert_eq!(offset_of!(RawBoardState, active_subboard), 82 * 4);
+
+        assert_eq!(size_of::<RawMove>(), 2 * 4);
+        assert_eq!(align_of::<RawMove>(), 4);
+        assert_eq!(offset_of!(RawMove, subboard), 0);
+        assert_eq!(offset_of!(RawMove, square), 4);
+    }
+
+    #[test]
+    fn header() {
+        let header = include_str!("../interface/rustbot.h");
+        assert!(header.contains(&format!("#define RUSTBOT_ABI_VERSION {}\n", ABI_VERSION)));
+        assert_eq!(rustbot_abi_version(), ABI_VERSION);
+        // The exported functions are exactly the ones declared. Naming the
+        // functions keeps this list from outliving a rename.
+        let exported = [
+            ("rustbot_abi_version",       rustbot_abi_version as *const ()),
+            ("get_move",                  get_move as *const ()),
+            ("rustbot_engine_new",        rustbot_engine_new as *const ()),
+            ("rustbot_engine_set_option", rustbot_engine_set_option as *const ()),
+            ("rustbot_engine_get_move",   rustbot_engine_get_move as *const ()),
+            ("rustbot_engine_free",       rustbot_engine_free as *const ()),
+        ];
+        let mut exported: Vec<_> = exported.iter().map(|&(symbol, _)| symbol).collect();
+        exported.sort();
+
+        // Declarations are the top level lines like `type name(parameters);`.
+        let mut declared: Vec<_> = header
+            .lines()
+            .filter(|line| !line.starts_with(char::is_whitespace) && !line.starts_with("//") && !line.starts_with('#'))
+            .filter(|line| !line.starts_with("typedef") && line.ends_with(");"))
+            .filter_map(|line| line.split_once('(').map(|(start, _)| start))
+            .filter_map(|start| start.rsplit([' ', '*']).next())
+            .collect();
+        declared.sort();
+        assert_eq!(declared, exported);
+    }
+}
@@ -0,0 +1,322 @@
+use std::{env, error::Error, ffi::{c_char, c_int, c_void, CStr}, fmt::Display, fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf}, sync::Mutex, time::Duration};
+
+use crate::{algorithms::engine::{engine_from_spec, Engine, EngineSpecError, Limits}, utils::json::{Json, JsonError}};
+
+// How the library plays, read from `rustbot.json` next to the library (or at
+// the path in `RUSTBOT_CONFIG`), then overridden by environment variables:
+//
+//     key             variable                 default
+//     engine          RUSTBOT_ENGINE           "minimax", with options as in `engine_from_spec`
+//     time_fraction   RUSTBOT_TIME_FRACTION    1/3 of the host's timeout per move
+//     timeout         RUSTBOT_TIMEOUT          the host's, from its `settings.json`, or 3 seconds; at most a day
+//     tt_mb           RUSTBOT_TT_MB            the engine's, for minimax only; at most 65536
+//     threads         RUSTBOT_THREADS          the engine's, for minimax only; at most 256
+//     log             RUSTBOT_LOG              standard error
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub engine:        String,
+    pub time_fraction: f64,
+    pub timeout:       Duration, // Per move, given by the host.
+    pub tt_mb:         Option<usize>,
+    pub threads:       Option<usize>,
+    pub log_path:      Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Json(JsonError),
+    InvalidValue(&'static str),
+    Engine(EngineSpecError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(error)       => write!(f, "invalid config: {}", error),
+            Self::InvalidValue(key) => write!(f, "invalid value for config \"{}\"", key),
+            Self::Engine(error)     => write!(f, "invalid engine in config: {}", error),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+const CONFIG_FILE_NAME: &str = "rustbot.json";
+const HOST_SETTINGS_PATH: &str = "settings.json";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_TT_MB: usize = 65536;
+const MAX_THREADS: usize = 256;
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            engine:        "minimax".to_owned(),
+            time_fraction: 1.0 / 3.0,
+            timeout:       DEFAULT_TIMEOUT,
+            tt_mb:         None,
+            threads:       None,
+            log_path:      None,
+        }
+    }
+}
+
+impl Config {
+    // Falls back to the defaults for anything that cannot be read, logging why
+    // once the log file is known.
+    pub fn load() -> Self {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+        if let Some(timeout) = host_timeout() {
+            config.timeout = timeout;
+        }
+
+        let path = env::var_os("RUSTBOT_CONFIG").map(PathBuf::from).or_else(|| {
+            library_directory().map(|directory| directory.join(CONFIG_FILE_NAME))
+        });
+        if let Some(path) = path.filter(|path| path.exists()) {
+            let read = fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|text| config.read_json(&text).map_err(|error| error.to_string()));
+            if let Err(error) = read {
+                errors.push(format!("rustbot: {}: {}", path.display(), error));
+            }
+        }
+        if let Err(error) = config.read_env(|name| env::var(name).ok()) {
+            errors.push(format!("rustbot: {}", error));
+        }
+        if let Err(error) = engine_from_spec(&config.engine_spec()) {
+            errors.push(format!("rustbot: {}, playing minimax", ConfigError::Engine(error)));
+            config.engine = Self::default().engine;
+        }
+
+        set_log_path(config.log_path.clone());
+        for error in errors {
+            log(&error);
+        }
+        config
+    }
+
+    // Only the keys present are changed.
+    pub fn read_json(&mut self, text: &str) -> Result<(), ConfigError> {
+        let json = Json::parse(text).map_err(ConfigError::Json)?;
+        if let Some(engine) = json.get("engine") {
+            self.engine = engine.as_str().ok_or(ConfigError::InvalidValue("engine"))?.to_owned();
+        }
+        let number = |key| json.get(key).map(|value| value.as_f64().ok_or(ConfigError::InvalidValue(key))).transpose();
+        if let Some(time_fraction) = number("time_fraction")? {
+            self.time_fraction = check_time_fraction(time_fraction)?;
+        }
+        if let Some(timeout) = number("timeout")? {
+            self.timeout = check_timeout(timeout)?;
+        }
+        if let Some(tt_mb) = number("tt_mb")? {
+            self.tt_mb = Some(check_count(tt_mb, MAX_TT_MB, "tt_mb")?);
+        }
+        if let Some(threads) = number("threads")? {
+            self.threads = Some(check_count(threads, MAX_THREADS, "threads")?);
+        }
+        if let Some(log_path) = json.get("log") {
+            self.log_path = Some(log_path.as_str().ok_or(ConfigError::InvalidValue("log"))?.into());
+        }
+        Ok(())
+    }
+
+    // Only the variables that are set are read.
+    pub fn read_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(engine) = var("RUSTBOT_ENGINE") {
+            self.engine = engine;
+        }
+        let number = |name, key| {
+            var(name).map(|value| value.parse::<f64>().map_err(|_| ConfigError::InvalidValue(key))).transpose()
+        };
+        if let Some(time_fraction) = number("RUSTBOT_TIME_FRACTION", "time_fraction")? {
+            self.time_fraction = check_time_fraction(time_fraction)?;
+        }
+        if let Some(timeout) = number("RUSTBOT_TIMEOUT", "timeout")? {
+            self.timeout = check_timeout(timeout)?;
+        }
+        if let Some(tt_mb) = number("RUSTBOT_TT_MB", "tt_mb")? {
+            self.tt_mb = Some(check_count(tt_mb, MAX_TT_MB, "tt_mb")?);
+        }
+        if let Some(threads) = number("RUSTBOT_THREADS", "threads")? {
+            self.threads = Some(check_count(threads, MAX_THREADS, "threads")?);
+        }
+        if let Some(log_path) = var("RUSTBOT_LOG") {
+            self.log_path = Some(log_path.into());
+        }
+        Ok(())
+    }
+
+    // The engine with the TT size and threads added, which only minimax has.
+    pub fn engine_spec(&self) -> String {
+        let (name, mut options) = match self.engine.split_once(':') {
+            Some((name, options)) => (name, vec![options.to_owned()]),
+            None                  => (self.engine.as_str(), Vec::new()),
+        };
+        if name == "minimax" {
+            options.extend(self.tt_mb.map(|tt_mb| format!("tt={}", tt_mb)));
+            options.extend(self.threads.map(|threads| format!("threads={}", threads)));
+        }
+        if options.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}:{}", name, options.join(","))
+        }
+    }
+
+    pub fn engine(&self) -> Result<Box<dyn Engine>, EngineSpecError> {
+        engine_from_spec(&self.engine_spec())
+    }
+
+    // Fields set directly are not checked, so an invalid fraction falls back
+    // to the default one.
+    pub fn limits(&self) -> Limits {
+        let time_fraction = check_time_fraction(self.time_fraction).unwrap_or(Self::default().time_fraction);
+        Limits {
+            time: Some(self.timeout.mul_f64(time_fraction)),
+            ..Limits::default()
+        }
+    }
+}
+
+fn check_time_fraction(time_fraction: f64) -> Result<f64, ConfigError> {
+    match time_fraction > 0.0 && time_fraction <= 1.0 {
+        true  => Ok(time_fraction),
+        false => Err(ConfigError::InvalidValue("time_fraction")),
+    }
+}
+
+fn check_timeout(timeout: f64) -> Result<Duration, ConfigError> {
+    Duration::try_from_secs_f64(timeout)
+        .ok()
+        .filter(|timeout| !timeout.is_zero() && *timeout <= MAX_TIMEOUT)
+        .ok_or(ConfigError::InvalidValue("timeout"))
+}
+
+fn check_count(count: f64, max: usize, key: &'static str) -> Result<usize, ConfigError> {
+    match count >= 1.0 && count <= max as f64 && count.fract() == 0.0 {
+        true  => Ok(count as usize),
+        false => Err(ConfigError::InvalidValue(key)),
+    }
+}
+
+// The TTT-masters host runs with its `settings.json` in the working directory.
+fn host_timeout() -> Option<Duration> {
+    let text = fs::read_to_string(HOST_SETTINGS_PATH).ok()?;
+    let timeout = Json::parse(&text).ok()?.get("timeout")?.as_f64()?;
+    check_timeout(timeout).ok()
+}
+
+#[repr(C)]
+struct DlInfo {
+    file_name:    *const c_char,
+    base_address: *mut c_void,
+    symbol_name:  *const c_char,
+    symbol:       *mut c_void,
+}
+
+#[link(name = "dl")]
+unsafe extern "C" {
+    fn dladdr(address: *const c_void, info: *mut DlInfo) -> c_int;
+}
+
+// The directory of the library (or executable) this code was loaded from.
+fn library_directory() -> Option<PathBuf> {
+    let mut info = DlInfo {
+        file_name:    std::ptr::null(),
+        base_address: std::ptr::null_mut(),
+        symbol_name:  std::ptr::null(),
+        symbol:       std::ptr::null_mut(),
+    };
+    // Any function of this library will do.
+    let found = unsafe { dladdr(library_directory as *const c_void, &mut info) };
+    if found == 0 || info.file_name.is_null() {
+        return None;
+    }
+    let file_name = unsafe { CStr::from_ptr(info.file_name) }.to_str().ok()?;
+    Path::new(file_name).parent().map(Path::to_path_buf)
+}
+
+static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn set_log_path(log_path: Option<PathBuf>) {
+    *LOG_PATH.lock().unwrap_or_else(|error| error.into_inner()) = log_path;
+}
+
+// Appends a line to the configured log file, or writes it to standard error.
+pub(super) fn log(message: &str) {
+    let log_path = LOG_PATH.lock().unwrap_or_else(|error| error.into_inner()).clone();
+    let Some(log_path) = log_path else {
+        eprintln!("{}", message);
+        return;
+    };
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .and_then(|mut file| writeln!(file, "{}", message));
+    if written.is_err() {
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{algorithms::engine::EngineSpecError, utils::json::JsonError};
+
+    use super::{Config, ConfigError};
+
+    #[test]
+    fn read_json() {
+        let mut config = Config::default();
+        config.read_json(r#"{ "engine": "minimax:depth=6", "time_fraction": 0.5, "timeout": 2, "tt_mb": 64, "threads": 2, "log": "bot.log" }"#).unwrap();
+        assert_eq!(config.engine_spec(), "minimax:depth=6,tt=64,threads=2");
+        assert_eq!(config.limits().time, Some(Duration::from_secs(1)));
+        assert_eq!(config.log_path.as_deref(), Some("bot.log".as_ref()));
+        assert!(config.engine().is_ok());
+
+        // Keys that are left out keep their values.
+        config.read_json(r#"{ "engine": "mcts" }"#).unwrap();
+        assert_eq!(config.engine_spec(), "mcts");
+        assert_eq!(config.time_fraction, 0.5);
+
+        assert_eq!(config.read_json("{"), Err(ConfigError::Json(JsonError::UnexpectedEnd)));
+        assert_eq!(config.read_json(r#"{ "engine": 1 }"#), Err(ConfigError::InvalidValue("engine")));
+        assert_eq!(config.read_json(r#"{ "time_fraction": 1.5 }"#), Err(ConfigError::InvalidValue("time_fraction")));
+        assert_eq!(config.read_json(r#"{ "timeout": 0 }"#), Err(ConfigError::InvalidValue("timeout")));
+        assert_eq!(config.read_json(r#"{ "timeout": 1e300 }"#), Err(ConfigError::InvalidValue("timeout")));
+        assert_eq!(config.read_json(r#"{ "threads": 1.5 }"#), Err(ConfigError::InvalidValue("threads")));
+        assert_eq!(config.read_json(r#"{ "threads": 1e20 }"#), Err(ConfigError::InvalidValue("threads")));
+        assert_eq!(config.read_json(r#"{ "tt_mb": 65537 }"#), Err(ConfigError::InvalidValue("tt_mb")));
+
+        // Values set directly cannot make the limits panic.
+        let config = Config { timeout: Duration::MAX, time_fraction: f64::NAN, ..Config::default() };
+        assert!(config.limits().time.is_some_and(|time| time < Duration::MAX));
+    }
+
+    #[test]
+    fn read_env() {
+        let mut config = Config::default();
+        config.read_env(|name| match name {
+            "RUSTBOT_ENGINE"        => Some("greedy".to_owned()),
+            "RUSTBOT_TIME_FRACTION" => Some("0.25".to_owned()),
+            "RUSTBOT_TIMEOUT"       => Some("4".to_owned()),
+            "RUSTBOT_TT_MB"         => Some("8".to_owned()),
+            _                       => None,
+        }).unwrap();
+        assert_eq!(config.engine_spec(), "greedy");
+        assert_eq!(config.limits().time, Some(Duration::from_secs(1)));
+        assert_eq!(config.tt_mb, Some(8));
+
+        config.read_env(|name| (name == "RUSTBOT_ENGINE").then(|| "minimax".to_owned())).unwrap();
+        assert_eq!(config.engine_spec(), "minimax:tt=8");
+
+        let invalid = config.read_env(|name| (name == "RUSTBOT_THREADS").then(|| "many".to_owned()));
+        assert_eq!(invalid, Err(ConfigError::InvalidValue("threads")));
+        config.engine = "random".to_owned();
+        assert!(matches!(config.engine(), Err(EngineSpecError::UnknownEngine(_))));
+    }
+}
@@ -0,0 +1,489 @@
+use std::{error::Error, ffi::{c_char, c_int, c_void, CStr, CString}, fmt::Display, fs, path::{Path, PathBuf}, sync::{mpsc::{self, RecvTimeoutError}, Arc}, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{client::{ForfeitReason, Game}, record::GameRecord, utils::{board_state::BoardState, json::{Json, JsonError}, pattern::PatternState, rng::Rng, FfiBoardState, Move, Place, Player, Spot}};
+
+// The keys of the `settings.json` read by the TTT-masters host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub timeout:         Duration, // Per move.
+    pub starting_pieces: u32, // Random moves played before the bots take over.
+    pub seed:            Option<u64>, // `None` picks a seed from the clock.
+    pub starting:        Player,
+    pub dot_path:        PathBuf,
+    pub cross_path:      PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    Json(JsonError),
+    MissingKey(&'static str),
+    InvalidValue(&'static str),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(error)       => write!(f, "invalid settings: {}", error),
+            Self::MissingKey(key)   => write!(f, "missing setting \"{}\"", key),
+            Self::InvalidValue(key) => write!(f, "invalid value for setting \"{}\"", key),
+        }
+    }
+}
+
+impl Error for SettingsError {}
+
+impl Settings {
+    pub fn parse(text: &str) -> Result<Self, SettingsError> {
+        let json = Json::parse(text).map_err(SettingsError::Json)?;
+        let get = |key| json.get(key).ok_or(SettingsError::MissingKey(key));
+        let number = |key| get(key)?.as_f64().ok_or(SettingsError::InvalidValue(key));
+        let string = |key| get(key)?.as_str().ok_or(SettingsError::InvalidValue(key));
+
+        let timeout = Duration::try_from_secs_f64(number("timeout")?)
+            .ok()
+            .filter(|timeout| !timeout.is_zero())
+            .ok_or(SettingsError::InvalidValue("timeout"))?;
+        let starting_pieces = number("starting_pieces")?;
+        if starting_pieces < 0.0 || starting_pieces.fract() != 0.0 {
+            return Err(SettingsError::InvalidValue("starting_pieces"));
+        }
+        // Negative seeds ask for a random game. Others are read as integers, as
+        // seeds above 2^53 do not survive a round trip through an `f64`.
+        let seed = if number("seed")? < 0.0 {
+            None
+        } else {
+            Some(get("seed")?.as_u64().ok_or(SettingsError::InvalidValue("seed"))?)
+        };
+        let starting = match string("starting")? {
+            "cross" => Player::Cross,
+            "dot"   => Player::Dot,
+            _       => return Err(SettingsError::InvalidValue("starting")),
+        };
+
+        Ok(Self {
+            timeout,
+            starting_pieces: starting_pieces as u32,
+            seed,
+            starting,
+            dot_path: string("dot_path")?.into(),
+            cross_path: string("cross_path")?.into(),
+        })
+    }
+
+    pub fn seed_or_random(&self) -> u64 {
+        self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos() as u64)
+                .unwrap_or_default()
+        })
+    }
+}
+
+// A bot as seen by the host, which checks whatever the bot answers.
+pub trait Bot: Send + Sync {
+    fn name(&self) -> &str;
+
+    // The subboard and square indices of the chosen move.
+    fn get_move(&self, board_state: &BoardState) -> (i32, i32);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotAbi {
+    Bot,     // `_move bot(raw_boardstate)` as compiled from `interface.cpp`.
+    GetMove, // `RawMove get_move(RawBoardState)` as exported by this crate.
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryError {
+    NotFound(PathBuf),
+    Open(String),
+    MissingSymbol(PathBuf),
+}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(path)      => write!(f, "no bot library found at {}", path.display()),
+            Self::Open(error)         => write!(f, "could not open bot library: {}", error),
+            Self::MissingSymbol(path) => write!(f, "{} exports neither \"bot\" nor \"get_move\"", path.display()),
+        }
+    }
+}
+
+impl Error for LibraryError {}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FfiMove {
+    sub:  i32,
+    spot: i32,
+}
+
+// The libstdc++ layout of `std::vector<T>`.
+#[repr(C)]
+struct CppVector<T> {
+    start:          *const T,
+    finish:         *const T,
+    end_of_storage: *const T,
+}
+
+impl<T> CppVector<T> {
+    fn from_slice(slice: &[T]) -> Self {
+        let range = slice.as_ptr_range();
+        Self {
+            start: range.start,
+            finish: range.end,
+            end_of_storage: range.end,
+        }
+    }
+}
+
+// The `raw_boardstate` of `interface.cpp`.
+#[repr(C)]
+struct CppBoardState {
+    board:   CppVector<CppVector<i32>>,
+    turn:    i32,
+    current: i16,
+}
+
+// `FfiBoardState` is the `ffi_safe_boardstate` of `interface.cpp`.
+type BotFn = unsafe extern "C" fn(*const CppBoardState) -> FfiMove;
+type GetMoveFn = unsafe extern "C" fn(FfiBoardState) -> FfiMove;
+
+const RTLD_NOW: c_int = 2;
+
+#[link(name = "dl")]
+unsafe extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *mut c_char;
+}
+
+// A bot library loaded with `dlopen`. It is never closed, as a timed out bot
+// may still be running.
+pub struct BotLibrary {
+    name:     String,
+    abi:      BotAbi,
+    function: *mut c_void,
+}
+
+// The library stays loaded for the life of the process.
+unsafe impl Send for BotLibrary {}
+unsafe impl Sync for BotLibrary {}
+
+impl BotLibrary {
+    // `path` is either a library or a directory holding one, like the `AI/bot/`
+    // directory `interface.cpp` is compiled into.
+    pub fn open(path: &Path) -> Result<Self, LibraryError> {
+        let library_path = if path.is_dir() {
+            let mut libraries: Vec<_> = fs::read_dir(path)
+                .map_err(|_| LibraryError::NotFound(path.to_owned()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "so"))
+                .collect();
+            libraries.sort();
+            libraries.into_iter().next().ok_or_else(|| LibraryError::NotFound(path.to_owned()))?
+        } else if path.is_file() {
+            path.to_owned()
+        } else {
+            return Err(LibraryError::NotFound(path.to_owned()));
+        };
+
+        // A bare file name would be looked up on the library search path.
+        let absolute_path = fs::canonicalize(&library_path).map_err(|_| LibraryError::NotFound(library_path.clone()))?;
+        let c_path = CString::new(absolute_path.as_os_str().as_encoded_bytes())
+            .map_err(|_| LibraryError::NotFound(library_path.clone()))?;
+        let handle = unsafe { dlopen(c_path.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            let error = unsafe { dlerror() };
+            let message = if error.is_null() {
+                library_path.display().to_string()
+            } else {
+                unsafe { CStr::from_ptr(error) }.to_string_lossy().into_owned()
+            };
+            return Err(LibraryError::Open(message));
+        }
+
+        for (symbol, abi) in [(c"bot", BotAbi::Bot), (c"get_move", BotAbi::GetMove)] {
+            let function = unsafe { dlsym(handle, symbol.as_ptr()) };
+            if !function.is_null() {
+                return Ok(Self {
+                    name: library_path.display().to_string(),
+                    abi,
+                    function,
+                });
+            }
+        }
+        Err(LibraryError::MissingSymbol(library_path))
+    }
+
+    pub fn abi(&self) -> BotAbi {
+        self.abi
+    }
+}
+
+impl Bot for BotLibrary {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_move(&self, board_state: &BoardState) -> (i32, i32) {
+        let ffi_board_state = FfiBoardState::from(board_state.to_raw());
+
+        let move_ = match self.abi {
+            BotAbi::Bot => {
+                let rows: Vec<_> = ffi_board_state.board.iter().map(|row| CppVector::from_slice(row)).collect();
+                let cpp_board_state = CppBoardState {
+                    board: CppVector::from_slice(&rows),
+                    turn: ffi_board_state.turn,
+                    current: ffi_board_state.current as i16,
+                };
+                // The C++ class is passed by invisible reference, and the callee
+                // only reads through it. `rows` and `ffi_board_state` outlive the call.
+                let function: BotFn = unsafe { std::mem::transmute(self.function) };
+                unsafe { function(&cpp_board_state) }
+            },
+            BotAbi::GetMove => {
+                let function: GetMoveFn = unsafe { std::mem::transmute(self.function) };
+                unsafe { function(ffi_board_state) }
+            },
+        };
+        (move_.sub, move_.spot)
+    }
+}
+
+#[derive(Debug)]
+pub struct HostedGame {
+    pub game:    Game<'static>,
+    pub opening: usize, // Random moves made by the host before the bots take over.
+}
+
+impl HostedGame {
+    pub fn opening_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.game.history()[..self.opening].iter().map(|recorded| recorded.move_)
+    }
+
+    pub fn bot_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.game.history()[self.opening..].iter().map(|recorded| recorded.move_)
+    }
+
+    // Like "cross won", after the forfeit that decided the game, if any.
+    pub fn outcome(&self) -> String {
+        let result = match self.game.result() {
+            PatternState::Won(Player::Cross) => "cross won",
+            PatternState::Won(Player::Dot)   => "dot won",
+            PatternState::Drawn              => "draw",
+            PatternState::Undecided          => "unfinished",
+        };
+        match self.game.forfeit() {
+            Some(forfeit) => format!("{}, {}", forfeit, result),
+            None          => result.to_owned(),
+        }
+    }
+
+    // The whole game including the opening, whose length is kept in the
+    // "StartingPieces" tag. Forfeits are explained in the "Termination" tag.
+    pub fn to_record(&self, settings: &Settings, cross: &str, dot: &str, seed: u64) -> GameRecord {
+        let mut record = self.game.to_record();
+        record.cross = cross.to_owned();
+        record.dot = dot.to_owned();
+        record.time_control = Some(format!("movetime {}", settings.timeout.as_millis()));
+        record.tags.push(("Seed".to_owned(), seed.to_string()));
+        record.tags.push(("StartingPieces".to_owned(), self.opening.to_string()));
+        record
+    }
+}
+
+// Plays one game as the TTT-masters host would: random starting pieces,
+// then the bots take turns, each forfeiting on a timeout, crash or illegal move.
+pub fn play_game(settings: &Settings, cross: Arc<dyn Bot>, dot: Arc<dyn Bot>, seed: u64) -> HostedGame {
+    let mut rng = Rng::new(seed);
+    let mut game = Game::from_position(BoardState::new_empty(settings.starting));
+    while game.history().len() < settings.starting_pieces as usize && !game.is_over() {
+        let move_ = *rng.choose(&game.legal_moves());
+        game.play(move_).expect("legal moves can be played");
+    }
+    let opening = game.history().len();
+
+    while !game.is_over() {
+        let bot = match game.side_to_move() {
+            Player::Cross => Arc::clone(&cross),
+            Player::Dot   => Arc::clone(&dot),
+        };
+
+        // A bot that times out is left running, as it cannot be stopped.
+        let (tx, rx) = mpsc::channel();
+        let board_state = *game.board_state();
+        thread::spawn(move || {
+            let _ = tx.send(bot.get_move(&board_state));
+        });
+        let reason = match rx.recv_timeout(settings.timeout) {
+            Ok((sub, spot)) => match move_from_indices(sub, spot) {
+                Some(move_) => match game.play(move_) {
+                    Ok(())     => continue,
+                    Err(error) => ForfeitReason::IllegalMove(move_, error),
+                },
+                None => ForfeitReason::OffBoard(sub, spot),
+            },
+            Err(RecvTimeoutError::Timeout)      => ForfeitReason::Timeout,
+            Err(RecvTimeoutError::Disconnected) => ForfeitReason::Crash,
+        };
+        game.forfeit_side_to_move(reason);
+    }
+
+    HostedGame {
+        game,
+        opening,
+    }
+}
+
+fn move_from_indices(sub: i32, spot: i32) -> Option<Move> {
+    if !(0..9).contains(&sub) || !(0..9).contains(&spot) {
+        return None;
+    }
+    Some(Move::new(Spot {
+        subboard: Place::from_index(sub as usize),
+        square:   Place::from_index(spot as usize),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use crate::{algorithms::greedy::greedy, client::ForfeitReason, utils::{board_state::{BoardState, MoveError}, json::JsonError, pattern::PatternState, Move, Place, Player, Spot}};
+
+    use super::{play_game, Bot, HostedGame, Settings, SettingsError};
+
+    const SETTINGS: &str = r#"{
+        "timeout": 3,
+        "starting_pieces": 0,
+        "seed": -1,
+        "dot_path": "AI/hand/",
+        "cross_path": "AI/bot/",
+        "starting": "cross"
+    }"#;
+
+    struct GreedyBot;
+
+    impl Bot for GreedyBot {
+        fn name(&self) -> &str {
+            "greedy"
+        }
+
+        fn get_move(&self, board_state: &BoardState) -> (i32, i32) {
+            let move_ = greedy(board_state);
+            (move_.subboard().to_index() as i32, move_.square().to_index() as i32)
+        }
+    }
+
+    struct FixedBot(i32, i32);
+
+    impl Bot for FixedBot {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn get_move(&self, _board_state: &BoardState) -> (i32, i32) {
+            (self.0, self.1)
+        }
+    }
+
+    struct SlowBot;
+
+    impl Bot for SlowBot {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn get_move(&self, _board_state: &BoardState) -> (i32, i32) {
+            thread::sleep(Duration::from_secs(1));
+            (0, 0)
+        }
+    }
+
+    struct CrashingBot;
+
+    impl Bot for CrashingBot {
+        fn name(&self) -> &str {
+            "crashing"
+        }
+
+        fn get_move(&self, _board_state: &BoardState) -> (i32, i32) {
+            panic!("crashing bot");
+        }
+    }
+
+    #[test]
+    fn parse_settings() {
+        let settings = Settings::parse(SETTINGS).unwrap();
+        assert_eq!(settings.timeout, Duration::from_secs(3));
+        assert_eq!(settings.starting_pieces, 0);
+        assert_eq!(settings.seed, None);
+        assert_eq!(settings.starting, Player::Cross);
+        assert_eq!(settings.dot_path.to_str(), Some("AI/hand/"));
+        assert_eq!(settings.cross_path.to_str(), Some("AI/bot/"));
+
+        let settings = Settings::parse(&SETTINGS.replace("-1", "42").replace("\"cross\"", "\"dot\"")).unwrap();
+        assert_eq!(settings.seed, Some(42));
+        assert_eq!(settings.starting, Player::Dot);
+        let settings = Settings::parse(&SETTINGS.replace("-1", "9007199254740993")).unwrap();
+        assert_eq!(settings.seed, Some(9_007_199_254_740_993));
+        assert_eq!(Settings::parse(&SETTINGS.replace("-1", "4.5")), Err(SettingsError::InvalidValue("seed")));
+
+        assert_eq!(Settings::parse("{"), Err(SettingsError::Json(JsonError::UnexpectedEnd)));
+        assert_eq!(Settings::parse("{}"), Err(SettingsError::MissingKey("timeout")));
+        assert_eq!(Settings::parse(&SETTINGS.replace("\"cross\"", "\"both\"")), Err(SettingsError::InvalidValue("starting")));
+        assert_eq!(Settings::parse(&SETTINGS.replace("3", "0")), Err(SettingsError::InvalidValue("timeout")));
+        assert_eq!(Settings::parse(&SETTINGS.replace("3", "1e300")), Err(SettingsError::InvalidValue("timeout")));
+    }
+
+    #[test]
+    fn greedy_game() {
+        let mut settings = Settings::parse(SETTINGS).unwrap();
+        settings.starting_pieces = 4;
+        let game = play_game(&settings, Arc::new(GreedyBot), Arc::new(GreedyBot), 7);
+        assert_eq!(game.opening, 4);
+        assert_eq!(game.opening_moves().count(), 4);
+        assert!(game.game.is_over());
+        assert_eq!(game.game.forfeit(), None);
+
+        // The same seed gives the same opening.
+        let record = game.to_record(&settings, "greedy", "greedy", 7);
+        let replayed = play_game(&settings, Arc::new(GreedyBot), Arc::new(GreedyBot), 7);
+        assert_eq!(replayed.to_record(&settings, "greedy", "greedy", 7), record);
+
+        assert_eq!(record.moves.len(), game.opening + game.bot_moves().count());
+        assert_eq!(record.final_state().map(|board_state| board_state.state()), Ok(record.result));
+        assert_eq!(record.to_string().parse(), Ok(record));
+    }
+
+    #[test]
+    fn forfeits() {
+        let mut settings = Settings::parse(SETTINGS).unwrap();
+        let forfeit = |game: &HostedGame| game.game.forfeit().map(|forfeit| (forfeit.player, forfeit.reason));
+
+        let game = play_game(&settings, Arc::new(FixedBot(4, 9)), Arc::new(GreedyBot), 0);
+        assert_eq!(forfeit(&game), Some((Player::Cross, ForfeitReason::OffBoard(4, 9))));
+
+        // The second move onto the same square is illegal.
+        let game = play_game(&settings, Arc::new(FixedBot(4, 4)), Arc::new(FixedBot(4, 4)), 0);
+        assert_eq!(game.bot_moves().count(), 1);
+        let move_ = Move::new(Spot { subboard: Place::MidMid, square: Place::MidMid });
+        assert_eq!(forfeit(&game), Some((Player::Dot, ForfeitReason::IllegalMove(move_, MoveError::SquareOccupied))));
+
+        let game = play_game(&settings, Arc::new(GreedyBot), Arc::new(CrashingBot), 0);
+        assert_eq!(forfeit(&game), Some((Player::Dot, ForfeitReason::Crash)));
+
+        settings.timeout = Duration::from_millis(50);
+        let game = play_game(&settings, Arc::new(SlowBot), Arc::new(GreedyBot), 0);
+        assert_eq!(forfeit(&game), Some((Player::Cross, ForfeitReason::Timeout)));
+        assert_eq!(game.game.result(), PatternState::Won(Player::Dot));
+        assert_eq!(game.outcome(), "cross forfeited on time, dot won");
+
+        let record = game.to_record(&settings, "slow", "greedy", 0);
+        assert_eq!(record.result, PatternState::Won(Player::Dot));
+        assert!(record.tags.contains(&("Termination".to_owned(), "cross forfeited on time".to_owned())));
+    }
+}
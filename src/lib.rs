@@ -1,16 +1,13 @@
-use utils::{board_state::BoardState, RawBoardState, RawMove};
-
 pub mod utils;
 pub mod algorithms {
+    pub mod engine;
     pub mod greedy;
     pub mod minimax;
+    pub mod mcts;
 }
 pub mod client;
-
-#[unsafe(no_mangle)]
-extern "C" fn get_move(raw_board_state: RawBoardState) -> RawMove {
-    let board_state = BoardState::from_raw(raw_board_state);
-    board_state.dbg_print();
-    let move_ = algorithms::minimax::minimax(&board_state);
-    move_.to_raw()
-}
+pub mod arena;
+pub mod host;
+pub mod protocol;
+pub mod record;
+pub mod ffi;
@@ -0,0 +1,527 @@
+use std::{error::Error, fmt::Display, io::Write, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread::{self, JoinHandle}, time::Duration};
+
+use crate::{algorithms::{engine::{engine_from_spec, Engine, Limits, SearchResult}, minimax::eval::{plies_to_loss, plies_to_win}}, ffi::panic_message, utils::{board_state::BoardState, pattern::PatternState, Move, Player}};
+
+// A UCI-style line protocol, for driving an engine as a subprocess.
+//
+// Commands:
+// * `uci`, answered with the engine id, its options and `uciok`.
+// * `isready`, answered with `readyok`.
+// * `ucinewgame`.
+// * `setoption name <name> value <value>`, with the options listed by `uci`.
+// * `position startpos [cross|dot] [moves <move>...]`, where the optional
+//   player moves first, or `position fen <position> [moves <move>...]` with a
+//   position in the notation of `utils::notation`.
+// * `go [movetime <ms>] [depth <plies>] [nodes <n>] [infinite]`, answered with
+//   `info` lines and a `bestmove`, which is `bestmove (none)` once the game
+//   is over.
+// * `stop`, ending the current search early.
+// * `quit`.
+//
+// Moves are written in the notation of `utils::notation`, like `e5`, and are
+// also read as `<subboard>/<square>`, like `4/4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Uci,
+    IsReady,
+    NewGame,
+    SetOption { name: String, value: String },
+    Position(BoardState),
+    Go(GoLimits),
+    Stop,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GoLimits {
+    pub move_time: Option<Duration>,
+    pub depth:     Option<u32>,
+    pub nodes:     Option<u64>,
+    pub infinite:  bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    UnknownCommand(String),
+    InvalidArgument(String),
+    IllegalMove(String),
+    UnknownOption(String),
+    InvalidOptionValue(String),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(command)    => write!(f, "unknown command \"{}\"", command),
+            Self::InvalidArgument(argument)  => write!(f, "invalid argument \"{}\"", argument),
+            Self::IllegalMove(move_)         => write!(f, "illegal move \"{}\"", move_),
+            Self::UnknownOption(name)        => write!(f, "unknown option \"{}\"", name),
+            Self::InvalidOptionValue(value)  => write!(f, "invalid option value \"{}\"", value),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+// Returns `None` for blank lines.
+pub fn parse_command(line: &str) -> Result<Option<Command>, ProtocolError> {
+    let mut tokens = line.split_whitespace();
+    let Some(command) = tokens.next() else {
+        return Ok(None);
+    };
+    let command = match command {
+        "uci"        => Command::Uci,
+        "isready"    => Command::IsReady,
+        "ucinewgame" => Command::NewGame,
+        "stop"       => Command::Stop,
+        "quit"       => Command::Quit,
+        "setoption"  => parse_setoption(tokens)?,
+        "position"   => Command::Position(parse_position(tokens)?),
+        "go"         => Command::Go(parse_go(tokens)?),
+        _            => return Err(ProtocolError::UnknownCommand(command.to_owned())),
+    };
+    Ok(Some(command))
+}
+
+fn parse_setoption<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Command, ProtocolError> {
+    if tokens.next() != Some("name") {
+        return Err(ProtocolError::InvalidArgument("expected name".to_owned()));
+    }
+    let mut name = Vec::new();
+    let mut value = Vec::new();
+    let mut in_value = false;
+    for token in tokens {
+        match token {
+            "value" if !in_value => in_value = true,
+            _ if in_value        => value.push(token),
+            _                    => name.push(token),
+        }
+    }
+    Ok(Command::SetOption {
+        name:  name.join(" "),
+        value: value.join(" "),
+    })
+}
+
+fn parse_position<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<BoardState, ProtocolError> {
+    let mut tokens = tokens.peekable();
+    let mut board_state = match tokens.next() {
+        Some("startpos") => {
+            let first_player = match tokens.peek() {
+                Some(&"cross") => Some(Player::Cross),
+                Some(&"dot")   => Some(Player::Dot),
+                _              => None,
+            };
+            if first_player.is_some() {
+                tokens.next();
+            }
+            BoardState::new_empty(first_player.unwrap_or(Player::Cross))
+        },
+        Some("fen") => {
+            let fields: Vec<_> = tokens.by_ref().take(3).collect();
+            let position = fields.join(" ");
+            position.parse().map_err(|_| ProtocolError::InvalidArgument(position))?
+        },
+        Some(token) => return Err(ProtocolError::InvalidArgument(token.to_owned())),
+        None        => return Err(ProtocolError::InvalidArgument("expected startpos or fen".to_owned())),
+    };
+
+    match tokens.next() {
+        Some("moves") => (),
+        Some(token)   => return Err(ProtocolError::InvalidArgument(token.to_owned())),
+        None          => return Ok(board_state),
+    }
+    for token in tokens {
+        let move_: Move = token.parse().map_err(|_| ProtocolError::InvalidArgument(token.to_owned()))?;
+        board_state = board_state
+            .try_do_move(move_)
+            .map_err(|_| ProtocolError::IllegalMove(token.to_owned()))?;
+    }
+    Ok(board_state)
+}
+
+fn parse_go<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<GoLimits, ProtocolError> {
+    let mut go_limits = GoLimits::default();
+    while let Some(token) = tokens.next() {
+        let mut value = || {
+            tokens
+                .next()
+                .and_then(|value| value.parse::<u64>().ok())
+                .ok_or_else(|| ProtocolError::InvalidArgument(token.to_owned()))
+        };
+        match token {
+            "movetime" => go_limits.move_time = Some(Duration::from_millis(value()?)),
+            "depth"    => {
+                let depth = u32::try_from(value()?).map_err(|_| ProtocolError::InvalidArgument(token.to_owned()))?;
+                go_limits.depth = Some(depth);
+            },
+            "nodes"    => go_limits.nodes = Some(value()?),
+            "infinite" => go_limits.infinite = true,
+            _          => return Err(ProtocolError::InvalidArgument(token.to_owned())),
+        }
+    }
+    Ok(go_limits)
+}
+
+// Forced results are given in moves, like UCI mate scores, and other evals in hundredths.
+pub fn format_info(result: &SearchResult) -> String {
+    let score = if let Some(plies) = plies_to_win(result.eval) {
+        format!("mate {}", plies.div_ceil(2))
+    } else if let Some(plies) = plies_to_loss(result.eval) {
+        format!("mate -{}", plies.div_ceil(2))
+    } else {
+        format!("cp {}", (result.eval * 100.0).round() as i64)
+    };
+    let principal_variation = result.principal_variation
+        .iter()
+        .map(Move::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "info depth {} score {} nodes {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.elapsed.as_millis(),
+        principal_variation,
+    )
+}
+
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+const DEFAULT_ENGINE: &str = "minimax";
+
+struct Search {
+    stop:   Arc<AtomicBool>,
+    thread: JoinHandle<Box<dyn Engine>>,
+}
+
+// The state of one protocol connection. Searches run on their own thread, so
+// that `stop` can be read while searching.
+pub struct Session {
+    output:          Output,
+    engine_name:     String,
+    hash_mb:         Option<usize>,
+    threads:         Option<usize>,
+    engine:          Option<Box<dyn Engine>>, // Lent to the search thread while searching.
+    search:          Option<Search>,
+    board_state:     BoardState,
+}
+
+impl Session {
+    pub fn new(output: Output) -> Self {
+        Self {
+            output,
+            engine_name: DEFAULT_ENGINE.to_owned(),
+            hash_mb: None,
+            threads: None,
+            engine: Some(engine_from_spec(DEFAULT_ENGINE).expect("default engine exists")),
+            search: None,
+            board_state: BoardState::new_empty(Player::Cross),
+        }
+    }
+
+    fn write_line(&self, line: &str) {
+        write_line(&self.output, line);
+    }
+
+    // Handles one line of input, returning `false` once the session should end.
+    pub fn handle_line(&mut self, line: &str) -> bool {
+        match parse_command(line) {
+            Ok(Some(command)) => self.handle(command),
+            Ok(None) => true,
+            Err(error) => {
+                self.write_line(&format!("info string {}", error));
+                true
+            },
+        }
+    }
+
+    pub fn handle(&mut self, command: Command) -> bool {
+        match command {
+            Command::Uci => {
+                self.write_line("id name rustbot");
+                self.write_line("option name Engine type combo default minimax var greedy var minimax var mcts");
+                self.write_line("option name Hash type spin default 32 min 1 max 65536");
+                self.write_line("option name Threads type spin default 1 min 1 max 256");
+                self.write_line("uciok");
+            },
+            Command::IsReady => {
+                self.write_line("readyok");
+            },
+            Command::NewGame => {
+                self.finish_search();
+                self.engine_mut().new_game();
+            },
+            Command::SetOption { name, value } => {
+                self.finish_search();
+                if let Err(error) = self.set_option(&name, &value) {
+                    self.write_line(&format!("info string {}", error));
+                }
+            },
+            Command::Position(board_state) => {
+                self.finish_search();
+                self.board_state = board_state;
+            },
+            Command::Go(go_limits) => {
+                self.finish_search();
+                self.go(go_limits);
+            },
+            Command::Stop => {
+                self.finish_search();
+            },
+            Command::Quit => {
+                self.finish_search();
+                return false;
+            },
+        }
+        true
+    }
+
+    fn engine_mut(&mut self) -> &mut Box<dyn Engine> {
+        self.engine.as_mut().expect("engine is only lent out while searching")
+    }
+
+    // Options are only taken once the engine they describe has been built.
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), ProtocolError> {
+        let invalid = || ProtocolError::InvalidOptionValue(value.to_owned());
+        let mut engine_name = self.engine_name.clone();
+        let mut hash_mb = self.hash_mb;
+        let mut threads = self.threads;
+        match name.to_lowercase().as_str() {
+            "engine"  => engine_name = value.to_owned(),
+            "hash"    => hash_mb = Some(value.parse().map_err(|_| invalid())?),
+            "threads" => threads = Some(value.parse().map_err(|_| invalid())?),
+            _         => return Err(ProtocolError::UnknownOption(name.to_owned())),
+        }
+
+        let engine = engine_from_spec(&engine_spec(&engine_name, hash_mb, threads)).map_err(|_| invalid())?;
+        self.engine_name = engine_name;
+        self.hash_mb = hash_mb;
+        self.threads = threads;
+        self.engine = Some(engine);
+        Ok(())
+    }
+
+    fn go(&mut self, go_limits: GoLimits) {
+        if self.board_state.state() != PatternState::Undecided {
+            self.write_line("info string game over");
+            self.write_line("bestmove (none)");
+            return;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let output = Arc::clone(&self.output);
+        let limits = Limits {
+            time: if go_limits.infinite || go_limits.depth.is_some() || go_limits.nodes.is_some() {
+                go_limits.move_time
+            } else {
+                go_limits.move_time.or(Limits::default().time)
+            },
+            depth: go_limits.depth,
+            nodes: go_limits.nodes,
+            stop: Some(Arc::clone(&stop)),
+            on_progress: Some(Arc::new(move |result: &SearchResult| {
+                write_line(&output, &format_info(result));
+            })),
+        };
+        let mut engine = self.engine.take().expect("engine is only lent out while searching");
+        let board_state = self.board_state;
+        let output = Arc::clone(&self.output);
+        let thread = thread::spawn(move || {
+            let result = engine.choose_move(&board_state, &limits);
+            write_line(&output, &format_info(&result));
+            write_line(&output, &format!("bestmove {}", result.best_move));
+            engine
+        });
+        self.search = Some(Search { stop, thread });
+    }
+
+    // Stops the running search, if any, and waits for its `bestmove`.
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            self.join_search(search);
+        }
+    }
+
+    // Waits for the running search to end on its own.
+    pub fn wait(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.join_search(search);
+        }
+    }
+
+    // Takes the engine back from a search. A search that panicked took its
+    // engine down with it, so a fresh one is built from the current options.
+    fn join_search(&mut self, search: Search) {
+        match search.thread.join() {
+            Ok(engine) => self.engine = Some(engine),
+            Err(payload) => {
+                self.write_line(&format!("info string search failed ({})", panic_message(&payload)));
+                self.write_line("bestmove (none)");
+                let spec = engine_spec(&self.engine_name, self.hash_mb, self.threads);
+                self.engine = Some(engine_from_spec(&spec).expect("current options are valid"));
+            },
+        }
+    }
+}
+
+// Hash and threads only apply to minimax.
+fn engine_spec(engine_name: &str, hash_mb: Option<usize>, threads: Option<usize>) -> String {
+    let mut spec = engine_name.to_owned();
+    if spec == "minimax" {
+        let options: Vec<_> = [("tt", hash_mb), ("threads", threads)]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+            .collect();
+        if !options.is_empty() {
+            spec = format!("{}:{}", spec, options.join(","));
+        }
+    }
+    spec
+}
+
+fn write_line(output: &Output, line: &str) {
+    let mut output = output.lock().expect("output lock is never poisoned");
+    // A closed output leaves nobody to report to.
+    let _ = writeln!(output, "{}", line);
+    let _ = output.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::{Arc, Mutex}, time::Duration};
+
+    use crate::utils::{board_state::BoardState, Move, Place, Player, Spot};
+
+    use super::{parse_command, Command, GoLimits, ProtocolError, Session};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_owned)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse_command("  "), Ok(None));
+        assert_eq!(parse_command("isready"), Ok(Some(Command::IsReady)));
+        assert_eq!(
+            parse_command("setoption name Hash value 64"),
+            Ok(Some(Command::SetOption { name: "Hash".to_owned(), value: "64".to_owned() })),
+        );
+        assert_eq!(
+            parse_command("go movetime 500 depth 4"),
+            Ok(Some(Command::Go(GoLimits {
+                move_time: Some(Duration::from_millis(500)),
+                depth: Some(4),
+                ..GoLimits::default()
+            }))),
+        );
+        assert_eq!(parse_command("go movetime x"), Err(ProtocolError::InvalidArgument("movetime".to_owned())));
+        assert_eq!(parse_command("go depth 4294967297"), Err(ProtocolError::InvalidArgument("depth".to_owned())));
+        assert_eq!(parse_command("jump"), Err(ProtocolError::UnknownCommand("jump".to_owned())));
+    }
+
+    #[test]
+    fn parse_position() {
+        let board_state = BoardState::new_empty(Player::Dot)
+            .do_move(Move::new(Spot { subboard: Place::MidMid, square: Place::TopLef }))
+            .do_move(Move::new(Spot { subboard: Place::TopLef, square: Place::BotRig }));
+        assert_eq!(parse_command("position startpos dot moves 4/0 0/8"), Ok(Some(Command::Position(board_state))));
+        assert_eq!(
+            parse_command("position startpos"),
+            Ok(Some(Command::Position(BoardState::new_empty(Player::Cross)))),
+        );
+        assert_eq!(
+            parse_command("position fen 9/9/9/3O5/9/9/9/9/9 X 0 moves 0/8"),
+            Ok(Some(Command::Position(board_state))),
+        );
+        assert_eq!(
+            parse_command("position fen 9/9/9 O 0"),
+            Err(ProtocolError::InvalidArgument("9/9/9 O 0".to_owned())),
+        );
+        assert_eq!(parse_command("position startpos moves 4/0 4/1"), Err(ProtocolError::IllegalMove("4/1".to_owned())));
+        assert_eq!(parse_command("position startpos moves 4/9"), Err(ProtocolError::InvalidArgument("4/9".to_owned())));
+    }
+
+    #[test]
+    fn parse_moves() {
+        let move_ = Move::new(Spot { subboard: Place::BotRig, square: Place::TopLef });
+        let board_state = BoardState::new_empty(Player::Cross).do_move(move_);
+        assert_eq!(parse_command("position startpos moves g3"), Ok(Some(Command::Position(board_state))));
+        assert_eq!(parse_command("position startpos moves 8/0"), Ok(Some(Command::Position(board_state))));
+        assert_eq!(parse_command("position startpos moves 80"), Err(ProtocolError::InvalidArgument("80".to_owned())));
+        assert_eq!(parse_command("position startpos moves -1/0"), Err(ProtocolError::InvalidArgument("-1/0".to_owned())));
+    }
+
+    #[test]
+    fn session() {
+        let buffer = SharedBuffer::default();
+        let mut session = Session::new(Arc::new(Mutex::new(buffer.clone())));
+        assert!(session.handle_line("uci"));
+        assert!(session.handle_line("isready"));
+        assert!(session.handle_line("setoption name Hash value 1"));
+        assert!(session.handle_line("position startpos moves 4/4"));
+        assert!(session.handle_line("go depth 2"));
+        session.wait();
+        assert!(session.handle_line("setoption name Engine value greedy"));
+        assert!(session.handle_line("go infinite"));
+        assert!(session.handle_line("stop"));
+        assert!(session.handle_line("setoption name Colour value red"));
+        assert!(!session.handle_line("quit"));
+
+        let lines = buffer.lines();
+        assert_eq!(lines.first().map(String::as_str), Some("id name rustbot"));
+        assert!(lines.contains(&"uciok".to_owned()));
+        assert!(lines.contains(&"readyok".to_owned()));
+        let best_moves: Vec<Move> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("bestmove "))
+            .map(|move_| move_.parse().unwrap())
+            .collect();
+        assert_eq!(best_moves.len(), 2);
+        assert!(best_moves.iter().all(|move_| move_.subboard() == Place::MidMid));
+        assert!(lines.iter().any(|line| line.starts_with("info depth 2 score cp ")));
+        assert_eq!(lines.last().map(String::as_str), Some("info string unknown option \"Colour\""));
+    }
+
+    #[test]
+    fn session_errors() {
+        let buffer = SharedBuffer::default();
+        let mut session = Session::new(Arc::new(Mutex::new(buffer.clone())));
+        // A rejected engine leaves the previous one in place, so its options still apply.
+        assert!(session.handle_line("setoption name Engine value chess"));
+        assert!(session.handle_line("setoption name Hash value 1"));
+        // Cross has taken the top row.
+        assert!(session.handle_line("position fen XXXXXXXXX/9/9/9/9/9/9/9/9 O -"));
+        assert!(session.handle_line("go depth 2"));
+        session.wait();
+
+        assert_eq!(
+            buffer.lines(),
+            [
+                "info string invalid option value \"chess\"",
+                "info string game over",
+                "bestmove (none)",
+            ],
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{algorithms::minimax::eval::Eval, utils::{board_state::BoardState, notation::ParsePositionError, pattern::PatternState, Move, Player}};
+
+// Games are stored in a PGN-like text format:
+//
+//     [Cross "alice"]
+//     [Dot "minimax:depth=4"]
+//     [DotEngine "minimax"]
+//     [TimeControl "movetime 100"]
+//     [Position "9/9/9/9/9/9/9/9/9 X -"]
+//     [Result "0-1"]
+//
+//     1. e5 d6 {-0.5} 2. b8 {1.25} ... 0-1
+//
+// Tags are written one per line, followed by a blank line and the numbered
+// moves in the notation of `utils::notation`, each optionally followed by the
+// eval of its search in braces, from the perspective of the player who made
+// it. The moves end with the result: "1-0" when cross won, "0-1" when dot won,
+// "1/2-1/2" for a draw and "*" for an unfinished game. Comments that are not
+// evals are skipped when reading, and a file may hold any number of games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub cross:        String,
+    pub dot:          String,
+    pub cross_engine: Option<String>,
+    pub dot_engine:   Option<String>,
+    pub time_control: Option<String>,
+    pub start:        BoardState,
+    pub result:       PatternState,
+    pub tags:         Vec<(String, String)>, // Any other tags, like "Seed".
+    pub moves:        Vec<RecordedMove>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedMove {
+    pub move_: Move,
+    pub eval:  Option<Eval>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    InvalidTag(String),
+    UnterminatedTag,
+    UnterminatedComment,
+    InvalidPosition(ParsePositionError),
+    InvalidMove(String),
+    IllegalMove(usize), // Moves are numbered from 0.
+    InvalidResult(String),
+    MissingResult,
+    ResultMismatch,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTag(tag)        => write!(f, "invalid tag \"{}\"", tag),
+            Self::UnterminatedTag        => write!(f, "unterminated tag"),
+            Self::UnterminatedComment    => write!(f, "unterminated comment"),
+            Self::InvalidPosition(error) => write!(f, "invalid position: {}", error),
+            Self::InvalidMove(move_)     => write!(f, "invalid move \"{}\"", move_),
+            Self::IllegalMove(index)     => write!(f, "illegal move {}", index + 1),
+            Self::InvalidResult(result)  => write!(f, "invalid result \"{}\"", result),
+            Self::MissingResult          => write!(f, "missing result after the moves"),
+            Self::ResultMismatch         => write!(f, "result after the moves differs from the result tag"),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+pub fn format_result(state: PatternState) -> &'static str {
+    match state {
+        PatternState::Won(Player::Cross) => "1-0",
+        PatternState::Won(Player::Dot)   => "0-1",
+        PatternState::Drawn              => "1/2-1/2",
+        PatternState::Undecided          => "*",
+    }
+}
+
+pub fn parse_result(string: &str) -> Option<PatternState> {
+    match string {
+        "1-0"     => Some(PatternState::Won(Player::Cross)),
+        "0-1"     => Some(PatternState::Won(Player::Dot)),
+        "1/2-1/2" => Some(PatternState::Drawn),
+        "*"       => Some(PatternState::Undecided),
+        _         => None,
+    }
+}
+
+impl GameRecord {
+    // An unfinished game without moves, between unnamed players.
+    pub fn new(start: BoardState) -> Self {
+        GameRecord {
+            cross:        "?".to_owned(),
+            dot:          "?".to_owned(),
+            cross_engine: None,
+            dot_engine:   None,
+            time_control: None,
+            start,
+            result:       PatternState::Undecided,
+            tags:         Vec::new(),
+            moves:        Vec::new(),
+        }
+    }
+
+    // The positions of the game, from the start to the last move.
+    pub fn replay(&self) -> Result<Vec<BoardState>, RecordError> {
+        let mut positions = vec![self.start];
+        for (index, recorded) in self.moves.iter().enumerate() {
+            let board_state = positions[index]
+                .try_do_move(recorded.move_)
+                .map_err(|_| RecordError::IllegalMove(index))?;
+            positions.push(board_state);
+        }
+        Ok(positions)
+    }
+
+    pub fn final_state(&self) -> Result<BoardState, RecordError> {
+        self.replay().map(|positions| positions[positions.len() - 1])
+    }
+
+    // Every game in `text`, in order.
+    pub fn read_all(text: &str) -> Result<Vec<Self>, RecordError> {
+        let mut tokens = tokenize(text)?.into_iter().peekable();
+        let mut records = Vec::new();
+        while tokens.peek().is_some() {
+            records.push(read_game(&mut tokens)?);
+        }
+        Ok(records)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Word(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, RecordError> {
+    let mut tokens = Vec::new();
+    let mut characters = text.char_indices().peekable();
+    while let Some(&(start, character)) = characters.peek() {
+        match character {
+            _ if character.is_whitespace() => {
+                characters.next();
+            },
+            '[' => {
+                let end = tag_end(&text[start..]).ok_or(RecordError::UnterminatedTag)? + start;
+                tokens.push(parse_tag(&text[start + 1..end])?);
+                while characters.next_if(|&(index, _)| index <= end).is_some() {}
+            },
+            '{' => {
+                characters.next();
+                let end = text[start..].find('}').ok_or(RecordError::UnterminatedComment)? + start;
+                tokens.push(Token::Comment(text[start + 1..end].trim().to_owned()));
+                while characters.next_if(|&(index, _)| index <= end).is_some() {}
+            },
+            _ => {
+                let end = text[start..]
+                    .find(|character: char| character.is_whitespace() || matches!(character, '[' | '{'))
+                    .map_or(text.len(), |end| end + start);
+                tokens.push(Token::Word(text[start..end].to_owned()));
+                while characters.next_if(|&(index, _)| index < end).is_some() {}
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+// The offset of the ']' closing the tag at the start of `text`.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, character) in text.char_indices() {
+        match character {
+            _ if escaped   => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"'            => quoted = !quoted,
+            ']' if !quoted => return Some(index),
+            _              => (),
+        }
+    }
+    None
+}
+
+// Tags are a name and a quoted value, like `Cross "alice"`.
+fn parse_tag(tag: &str) -> Result<Token, RecordError> {
+    let invalid = || RecordError::InvalidTag(tag.to_owned());
+    let (name, value) = tag.trim().split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = value
+        .trim_start()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    let mut unescaped = String::new();
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => unescaped.push(characters.next().ok_or_else(invalid)?),
+            '"'  => return Err(invalid()),
+            _    => unescaped.push(character),
+        }
+    }
+    Ok(Token::Tag(name.to_owned(), unescaped))
+}
+
+fn read_game(tokens: &mut impl Iterator<Item = Token>) -> Result<GameRecord, RecordError> {
+    let mut record = GameRecord::new(BoardState::new_empty(Player::Cross));
+    let mut result_tag = None;
+    let mut board_state = None;
+    for token in tokens {
+        match token {
+            Token::Tag(name, value) if board_state.is_none() => match name.as_str() {
+                "Cross"       => record.cross = value,
+                "Dot"         => record.dot = value,
+                "CrossEngine" => record.cross_engine = Some(value),
+                "DotEngine"   => record.dot_engine = Some(value),
+                "TimeControl" => record.time_control = Some(value),
+                "Position"    => record.start = value.parse().map_err(RecordError::InvalidPosition)?,
+                "Result"      => result_tag = Some(parse_result(&value).ok_or(RecordError::InvalidResult(value))?),
+                _             => record.tags.push((name, value)),
+            },
+            Token::Tag(..) => return Err(RecordError::MissingResult),
+            Token::Comment(comment) => {
+                // Anything that is not an eval is an ordinary comment.
+                if let (Some(recorded), Ok(eval)) = (record.moves.last_mut(), comment.parse()) {
+                    recorded.eval = Some(eval);
+                }
+            },
+            Token::Word(word) => {
+                let board_state = board_state.get_or_insert(record.start);
+                if let Some(result) = parse_result(&word) {
+                    if result_tag.is_some_and(|result_tag| result_tag != result) {
+                        return Err(RecordError::ResultMismatch);
+                    }
+                    record.result = result;
+                    return Ok(record);
+                }
+                // Move numbers, like "3." or "3...".
+                if word.ends_with('.') && word.trim_end_matches('.').parse::<u32>().is_ok() {
+                    continue;
+                }
+                let move_: Move = word.parse().map_err(|_| RecordError::InvalidMove(word.clone()))?;
+                *board_state = board_state
+                    .try_do_move(move_)
+                    .map_err(|_| RecordError::IllegalMove(record.moves.len()))?;
+                record.moves.push(RecordedMove { move_, eval: None });
+            },
+        }
+    }
+    Err(RecordError::MissingResult)
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    // Reads a single game.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut records = GameRecord::read_all(string)?;
+        match records.len() {
+            1 => Ok(records.remove(0)),
+            _ => Err(RecordError::MissingResult),
+        }
+    }
+}
+
+fn write_tag(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Lines of moves are wrapped before this length.
+const LINE_LENGTH: usize = 80;
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tag(f, "Cross", &self.cross)?;
+        write_tag(f, "Dot", &self.dot)?;
+        if let Some(engine) = &self.cross_engine {
+            write_tag(f, "CrossEngine", engine)?;
+        }
+        if let Some(engine) = &self.dot_engine {
+            write_tag(f, "DotEngine", engine)?;
+        }
+        if let Some(time_control) = &self.time_control {
+            write_tag(f, "TimeControl", time_control)?;
+        }
+        write_tag(f, "Position", &self.start.to_string())?;
+        write_tag(f, "Result", format_result(self.result))?;
+        for (name, value) in &self.tags {
+            write_tag(f, name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut words = Vec::new();
+        let mut turn = self.start.turn();
+        for (index, recorded) in self.moves.iter().enumerate() {
+            let number = (index + usize::from(self.start.turn() == Player::Dot)) / 2 + 1;
+            if turn == Player::Cross {
+                words.push(format!("{}.", number));
+            } else if index == 0 {
+                words.push(format!("{}...", number));
+            }
+            words.push(recorded.move_.to_string());
+            if let Some(eval) = recorded.eval {
+                words.push(format!("{{{}}}", eval));
+            }
+            turn = turn.opposite();
+        }
+        words.push(format_result(self.result).to_owned());
+
+        let mut line_length = 0;
+        for word in words {
+            if line_length > 0 && line_length + 1 + word.len() > LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", word)?;
+            line_length += word.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{board_state::BoardState, pattern::PatternState, Move, Player};
+
+    use super::{GameRecord, RecordError, RecordedMove};
+
+    fn sample() -> GameRecord {
+        let mut record = GameRecord::new(BoardState::new_empty(Player::Cross));
+        record.cross = "alice".to_owned();
+        record.dot = "bot \"two\"".to_owned();
+        record.dot_engine = Some("minimax".to_owned());
+        record.time_control = Some("movetime 100".to_owned());
+        record.tags.push(("Seed".to_owned(), "7".to_owned()));
+        for (move_, eval) in [("e5", None), ("e6", Some(-0.5)), ("e8", Some(1.25))] {
+            record.moves.push(RecordedMove { move_: move_.parse().unwrap(), eval });
+        }
+        record
+    }
+
+    #[test]
+    fn write() {
+        assert_eq!(sample().to_string(), "\
+[Cross \"alice\"]
+[Dot \"bot \\\"two\\\"\"]
+[DotEngine \"minimax\"]
+[TimeControl \"movetime 100\"]
+[Position \"9/9/9/9/9/9/9/9/9 X -\"]
+[Result \"*\"]
+[Seed \"7\"]
+
+1. e5 e6 {-0.5} 2. e8 {1.25} *
+");
+    }
+
+    #[test]
+    fn round_trip() {
+        let record = sample();
+        assert_eq!(record.to_string().parse(), Ok(record.clone()));
+
+        // Games starting with dot, long enough to wrap, and finished.
+        let mut record = GameRecord::new(BoardState::new_empty(Player::Dot));
+        let mut board_state = record.start;
+        while board_state.state() == PatternState::Undecided {
+            let move_ = board_state.eligible_moves()[0];
+            record.moves.push(RecordedMove { move_, eval: Some(0.1) });
+            board_state = board_state.do_move(move_);
+        }
+        record.result = board_state.state();
+        let text = record.to_string();
+        assert!(text.contains("\n1... "));
+        assert!(text.lines().all(|line| line.len() <= 80));
+        assert_eq!(text.parse(), Ok(record.clone()));
+        assert_eq!(record.final_state(), Ok(board_state));
+
+        let records = GameRecord::read_all(&format!("{}\n{}", sample(), text)).unwrap();
+        assert_eq!(records, vec![sample(), record]);
+    }
+
+    #[test]
+    fn read() {
+        let record: GameRecord = "[Result \"1-0\"] 1. e5 {good move} e4 {0.5} 1-0".parse().unwrap();
+        assert_eq!(record.cross, "?");
+        assert_eq!(record.result, PatternState::Won(Player::Cross));
+        assert_eq!(record.moves[0].eval, None);
+        assert_eq!(record.moves[1].eval, Some(0.5));
+
+        let record: GameRecord = "[Position \"9/9/9/9/4X4/9/9/9/9 O 4\"] d6 *".parse().unwrap();
+        assert_eq!(record.moves.len(), 1);
+        assert_eq!(record.replay().unwrap().len(), 2);
+        assert_eq!(GameRecord::read_all(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn read_errors() {
+        assert_eq!("[Cross alice] *".parse::<GameRecord>(), Err(RecordError::InvalidTag("Cross alice".to_owned())));
+        assert_eq!("[Cross \"alice\" *".parse::<GameRecord>(), Err(RecordError::UnterminatedTag));
+        assert_eq!("e5 {0.5 *".parse::<GameRecord>(), Err(RecordError::UnterminatedComment));
+        assert_eq!("e5 j5 *".parse::<GameRecord>(), Err(RecordError::InvalidMove("j5".to_owned())));
+        assert_eq!("e5 a1 *".parse::<GameRecord>(), Err(RecordError::IllegalMove(1)));
+        assert_eq!("[Result \"2-0\"] *".parse::<GameRecord>(), Err(RecordError::InvalidResult("2-0".to_owned())));
+        assert_eq!("[Result \"1-0\"] e5 0-1".parse::<GameRecord>(), Err(RecordError::ResultMismatch));
+        assert_eq!("e5 e4".parse::<GameRecord>(), Err(RecordError::MissingResult));
+        assert!(matches!("[Position \"9/9\"] *".parse::<GameRecord>(), Err(RecordError::InvalidPosition(_))));
+
+        let mut record = GameRecord::new(BoardState::new_empty(Player::Cross));
+        record.moves.push(RecordedMove { move_: Move::from_global_index(0), eval: None });
+        record.moves.push(RecordedMove { move_: Move::from_global_index(80), eval: None });
+        assert_eq!(record.replay(), Err(RecordError::IllegalMove(1)));
+    }
+}
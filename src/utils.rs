@@ -2,14 +2,24 @@ use self::pattern::{Pattern, PatternState};
 
 use crate::utils::raw::{RawPlace, RawTurn};
 
-pub use crate::utils::raw::{RawBoardState, RawMove};
+pub use crate::utils::raw::{FfiBoardState, RawBoardState, RawMove};
 
 pub mod pattern;
 
+pub mod bitboard;
+
+mod zobrist;
+
 pub mod board_state;
 
 pub mod debug;
 
+pub mod rng;
+
+pub mod json;
+
+pub mod notation;
+
 mod raw;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -33,7 +43,7 @@ pub enum Place {
 }
 
 impl Place {
-    fn from_index(index: usize) -> Self {
+    pub(crate) fn from_index(index: usize) -> Self {
         match index {
             0 => Self::TopLef,
             1 => Self::TopMid,
@@ -48,7 +58,7 @@ impl Place {
         }
     }
 
-    fn to_index(self) -> usize {
+    pub(crate) fn to_index(self) -> usize {
         match self {
             Self::TopLef => 0,
             Self::TopMid => 1,
@@ -131,11 +141,19 @@ impl Player {
             RawTurn::Dot   => Player::Dot,
         }
     }
+
+    pub(crate) fn to_raw(self) -> RawTurn {
+        match self {
+            Player::Cross => RawTurn::Cross,
+            Player::Dot   => RawTurn::Dot,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Subboard {
     Won(Player),
+    Drawn,
     Active  (Pattern),
     Inactive(Pattern),
 }
@@ -152,6 +170,7 @@ impl Subboard {
     pub fn from_pattern(pattern: Pattern, active: bool) -> Self {
         match pattern.state() {
             PatternState::Won(player) => Subboard::Won(player),
+            PatternState::Drawn       => Subboard::Drawn,
             PatternState::Undecided if active => Subboard::Active  (pattern),
             PatternState::Undecided           => Subboard::Inactive(pattern),
         }
@@ -171,6 +190,10 @@ impl Subboard {
             _ => None,
         }
     }
+
+    pub fn is_decided(&self) -> bool {
+        matches!(self, Subboard::Won(_) | Subboard::Drawn)
+    }
 }
 
 
@@ -294,6 +317,18 @@ mod tests {
             let inactive_subboard = Subboard::from_pattern(undecided_pattern, false);
             assert!(matches!(inactive_subboard, Subboard::Inactive(_)));
             assert!(inactive_subboard.pattern_if_active().is_none());
+
+            let drawn_pattern = Pattern::dbg_from_matrix([
+                "X O X",
+                "X O O",
+                "O X O",
+            ]);
+
+            let drawn_subboard = Subboard::from_pattern(drawn_pattern, true);
+            assert_eq!(Subboard::Drawn, drawn_subboard);
+            assert!(drawn_subboard.is_decided());
+            assert!(drawn_subboard.pattern_if_undecided().is_none());
+            assert!(!active_subboard.is_decided());
         }
     }
 
@@ -0,0 +1,153 @@
+use std::ops::{BitAnd, BitOr, Not};
+
+use super::Place;
+
+// A set of places on a 3 by 3 grid, with bit `i` standing for `Place::from_index(i)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(u16);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL:  Bitboard = Bitboard(0b111_111_111);
+
+    pub const LINES: [Bitboard; 8] = [
+        Bitboard(0b000_000_111),
+        Bitboard(0b000_111_000),
+        Bitboard(0b111_000_000),
+        Bitboard(0b001_001_001),
+        Bitboard(0b010_010_010),
+        Bitboard(0b100_100_100),
+        Bitboard(0b100_010_001),
+        Bitboard(0b001_010_100),
+    ];
+
+    pub fn from_place(place: Place) -> Self {
+        Bitboard(1 << place.to_index())
+    }
+
+    pub fn contains(self, place: Place) -> bool {
+        self.0 & (1 << place.to_index()) != 0
+    }
+
+    pub fn with(self, place: Place) -> Self {
+        self | Self::from_place(place)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Self::EMPTY
+    }
+
+    pub fn is_full(self) -> bool {
+        self == Self::FULL
+    }
+
+    pub fn has_line(self) -> bool {
+        Self::LINES
+            .iter()
+            .any(|line| self & *line == *line)
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn places(self) -> BitboardPlaces {
+        BitboardPlaces {
+            remaining: self.0,
+        }
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Bitboard(!self.0 & Self::FULL.0)
+    }
+}
+
+pub struct BitboardPlaces {
+    remaining: u16,
+}
+
+impl Iterator for BitboardPlaces {
+    type Item = Place;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.remaining.trailing_zeros() as usize;
+        self.remaining &= self.remaining - 1;
+        Some(Place::from_index(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{pattern::Pattern, Piece, Place};
+
+    use super::Bitboard;
+
+    #[test]
+    fn from_place_contains_with() {
+        let bitboard = Bitboard::from_place(Place::TopMid);
+        assert!( bitboard.contains(Place::TopMid));
+        assert!(!bitboard.contains(Place::TopLef));
+
+        let bitboard = bitboard.with(Place::BotRig);
+        assert!(bitboard.contains(Place::TopMid));
+        assert!(bitboard.contains(Place::BotRig));
+        assert_eq!(bitboard.count(), 2);
+    }
+
+    #[test]
+    fn empty_full_not() {
+        assert!(Bitboard::EMPTY.is_empty());
+        assert!(Bitboard::FULL.is_full());
+        assert_eq!(!Bitboard::EMPTY, Bitboard::FULL);
+        assert_eq!(!Bitboard::FULL, Bitboard::EMPTY);
+        assert_eq!((!Bitboard::from_place(Place::MidMid)).count(), 8);
+    }
+
+    #[test]
+    fn lines_match_winning_patterns() {
+        for (line, pattern) in Bitboard::LINES.iter().zip(Pattern::WINNING_PATTERNS_CROSS) {
+            assert_eq!(*line, pattern.bitboard(Piece::Cross));
+            assert!(line.has_line());
+        }
+
+        let no_line = Bitboard::from_place(Place::TopLef)
+            .with(Place::TopMid)
+            .with(Place::MidRig)
+            .with(Place::BotLef);
+        assert!(!no_line.has_line());
+        assert!(no_line.with(Place::TopRig).has_line());
+    }
+
+    #[test]
+    fn places() {
+        let bitboard = Bitboard::from_place(Place::BotRig)
+            .with(Place::TopLef)
+            .with(Place::MidMid);
+        let places = bitboard.places().collect::<Vec<_>>();
+        assert_eq!(places, vec![Place::TopLef, Place::MidMid, Place::BotRig]);
+        assert_eq!(Bitboard::EMPTY.places().count(), 0);
+    }
+}
@@ -1,55 +1,220 @@
-use std::iter::Enumerate;
+use std::{fmt, hash::{Hash, Hasher}, sync::OnceLock};
 
 use crate::utils::pattern::PatternState;
 
-use super::{pattern::Pattern, raw::RawActiveSubBoard, Move, Piece, Place, Player, RawBoardState, Spot, Subboard};
+use super::{bitboard::Bitboard, pattern::Pattern, raw::RawActiveSubBoard, zobrist, FfiBoardState, Move, Piece, Place, Player, RawBoardState, Spot, Subboard};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    SubboardWon,
+    SubboardInactive,
+    SquareOccupied,
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MoveError::SubboardWon      => "subboard is won",
+            MoveError::SubboardInactive => "subboard is inactive",
+            MoveError::SquareOccupied   => "square is non-empty",
+            MoveError::GameOver         => "game is over",
+        })
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+// Why a board state received over FFI cannot be played on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidBoardState {
+    InvalidPiece { subboard: usize, square: usize, value: i32 },
+    InvalidTurn(i32),
+    InvalidActiveSubboard(i32),
+    PieceCounts { crosses: u32, dots: u32, turn: Player },
+    ActiveSubboardDecided(usize),
+}
+
+impl fmt::Display for InvalidBoardState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPiece { subboard, square, value } =>
+                write!(f, "invalid piece {} on square {} of subboard {}", value, square, subboard),
+            Self::InvalidTurn(turn)              => write!(f, "invalid turn {}", turn),
+            Self::InvalidActiveSubboard(current) => write!(f, "invalid active subboard {}", current),
+            Self::PieceCounts { crosses, dots, turn } =>
+                write!(f, "{} crosses and {} dots cannot have {:?} to move", crosses, dots, turn),
+            Self::ActiveSubboardDecided(index)   => write!(f, "active subboard {} is decided", index),
+        }
+    }
+}
+
+impl std::error::Error for InvalidBoardState {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BoardState {
-    board: [Subboard; 9],
+    crosses:   [Bitboard; 9],
+    dots:      [Bitboard; 9],
+    won_cross: Bitboard, // Subboards won by cross.
+    won_dot:   Bitboard, // Subboards won by dot.
+    drawn:     Bitboard, // Subboards drawn.
+    active:    Bitboard, // Subboards that may be played on.
     turn: Player,
+    key: u64, // Zobrist key of pieces, turn and active subboards.
+}
+
+// Equal board states always have equal keys, so hashing the key alone is enough.
+impl Hash for BoardState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
 }
 
 impl BoardState {
     pub fn new_empty(turn: Player) -> Self {
-        let board = [Subboard::new_empty(); 9];
         BoardState {
-            board,
+            crosses:   [Bitboard::EMPTY; 9],
+            dots:      [Bitboard::EMPTY; 9],
+            won_cross: Bitboard::EMPTY,
+            won_dot:   Bitboard::EMPTY,
+            drawn:     Bitboard::EMPTY,
+            active:    Bitboard::FULL,
             turn,
+            key: zobrist::active_key(Bitboard::FULL) ^ zobrist::turn_key(turn),
         }
     }
 
     pub fn from_raw(raw_board_state: RawBoardState) -> Self {
-        let board = std::array::from_fn(|subboard_index| {
-            let pattern = Pattern::from_raw(raw_board_state.board[subboard_index]);
-
-            let active = match raw_board_state.active_subboard {
-                RawActiveSubBoard::All    => true,
-                RawActiveSubBoard::TopLef => subboard_index == 0,
-                RawActiveSubBoard::TopMid => subboard_index == 1,
-                RawActiveSubBoard::TopRig => subboard_index == 2,
-                RawActiveSubBoard::MidLef => subboard_index == 3,
-                RawActiveSubBoard::MidMid => subboard_index == 4,
-                RawActiveSubBoard::MidRig => subboard_index == 5,
-                RawActiveSubBoard::BotLef => subboard_index == 6,
-                RawActiveSubBoard::BotMid => subboard_index == 7,
-                RawActiveSubBoard::BotRig => subboard_index == 8,
-            };
-            Subboard::from_pattern(pattern, active)
+        let patterns = raw_board_state.board.map(Pattern::from_raw);
+
+        let active = match raw_board_state.active_subboard {
+            RawActiveSubBoard::All    => Bitboard::FULL,
+            RawActiveSubBoard::TopLef => Bitboard::from_place(Place::TopLef),
+            RawActiveSubBoard::TopMid => Bitboard::from_place(Place::TopMid),
+            RawActiveSubBoard::TopRig => Bitboard::from_place(Place::TopRig),
+            RawActiveSubBoard::MidLef => Bitboard::from_place(Place::MidLef),
+            RawActiveSubBoard::MidMid => Bitboard::from_place(Place::MidMid),
+            RawActiveSubBoard::MidRig => Bitboard::from_place(Place::MidRig),
+            RawActiveSubBoard::BotLef => Bitboard::from_place(Place::BotLef),
+            RawActiveSubBoard::BotMid => Bitboard::from_place(Place::BotMid),
+            RawActiveSubBoard::BotRig => Bitboard::from_place(Place::BotRig),
+        };
+
+        Self::from_patterns(patterns, Player::from_raw(raw_board_state.turn), active)
+    }
+
+    // Unlike `from_raw`, rejects positions that no game can reach: the players
+    // alternate, so the player to move has as many pieces as the other or one
+    // fewer, and the active subboard must be undecided.
+    pub fn from_ffi(ffi_board_state: FfiBoardState) -> Result<Self, InvalidBoardState> {
+        let raw_board_state = RawBoardState::try_from(ffi_board_state)?;
+        let board_state = Self::from_raw(raw_board_state);
+
+        let crosses: u32 = board_state.crosses.iter().map(|bitboard| bitboard.count()).sum();
+        let dots:    u32 = board_state.dots   .iter().map(|bitboard| bitboard.count()).sum();
+        let (own, other) = match board_state.turn {
+            Player::Cross => (crosses, dots),
+            Player::Dot   => (dots, crosses),
+        };
+        if own != other && own + 1 != other {
+            return Err(InvalidBoardState::PieceCounts { crosses, dots, turn: board_state.turn });
+        }
+
+        if let Ok(index) = usize::try_from(ffi_board_state.current)
+            && board_state.decided().contains(Place::from_index(index))
+        {
+            return Err(InvalidBoardState::ActiveSubboardDecided(index));
+        }
+        Ok(board_state)
+    }
+
+    pub fn to_raw(&self) -> RawBoardState {
+        let board = std::array::from_fn(|index| {
+            Pattern::from_bitboards(self.crosses[index], self.dots[index]).to_raw()
         });
-        
-        BoardState {
+
+        // Several active subboards can only be all undecided ones.
+        let mut active_places = self.active.places();
+        let active_subboard = match (active_places.next(), active_places.next()) {
+            (Some(Place::TopLef), None) => RawActiveSubBoard::TopLef,
+            (Some(Place::TopMid), None) => RawActiveSubBoard::TopMid,
+            (Some(Place::TopRig), None) => RawActiveSubBoard::TopRig,
+            (Some(Place::MidLef), None) => RawActiveSubBoard::MidLef,
+            (Some(Place::MidMid), None) => RawActiveSubBoard::MidMid,
+            (Some(Place::MidRig), None) => RawActiveSubBoard::MidRig,
+            (Some(Place::BotLef), None) => RawActiveSubBoard::BotLef,
+            (Some(Place::BotMid), None) => RawActiveSubBoard::BotMid,
+            (Some(Place::BotRig), None) => RawActiveSubBoard::BotRig,
+            _                           => RawActiveSubBoard::All,
+        };
+
+        RawBoardState {
             board,
-            turn: Player::from_raw(raw_board_state.turn),
+            turn: self.turn.to_raw(),
+            active_subboard,
         }
     }
 
+    pub(super) fn from_patterns(patterns: [Pattern; 9], turn: Player, active: Bitboard) -> Self {
+        let mut board_state = BoardState::new_empty(turn);
+
+        for (index, pattern) in patterns.iter().enumerate() {
+            let place = Place::from_index(index);
+            board_state.crosses[index] = pattern.bitboard(Piece::Cross);
+            board_state.dots   [index] = pattern.bitboard(Piece::Dot);
+            match pattern.state() {
+                PatternState::Won(Player::Cross) => board_state.won_cross = board_state.won_cross.with(place),
+                PatternState::Won(Player::Dot)   => board_state.won_dot   = board_state.won_dot  .with(place),
+                PatternState::Drawn              => board_state.drawn     = board_state.drawn    .with(place),
+                PatternState::Undecided          => (),
+            }
+        }
+        board_state.active = active & !board_state.decided();
+        board_state.key = board_state.compute_key();
+
+        board_state
+    }
+
+    fn compute_key(&self) -> u64 {
+        let mut key = zobrist::active_key(self.active) ^ zobrist::turn_key(self.turn);
+        for index in 0..9 {
+            let subboard = Place::from_index(index);
+            for square in self.crosses[index].places() {
+                key ^= zobrist::piece_key(Player::Cross, subboard, square);
+            }
+            for square in self.dots[index].places() {
+                key ^= zobrist::piece_key(Player::Dot, subboard, square);
+            }
+        }
+        key
+    }
+
+    pub fn zobrist_key(&self) -> u64 {
+        self.key
+    }
+
     pub fn turn(&self) -> Player {
         self.turn
     }
     
     pub fn subboard(&self, subboard: Place) -> Subboard {
-        self.board[subboard.to_index()]
+        if self.won_cross.contains(subboard) {
+            return Subboard::Won(Player::Cross);
+        }
+        if self.won_dot.contains(subboard) {
+            return Subboard::Won(Player::Dot);
+        }
+        if self.drawn.contains(subboard) {
+            return Subboard::Drawn;
+        }
+
+        let index = subboard.to_index();
+        let pattern = Pattern::from_bitboards(self.crosses[index], self.dots[index]);
+        if self.active.contains(subboard) {
+            Subboard::Active(pattern)
+        } else {
+            Subboard::Inactive(pattern)
+        }
     }
 
     pub fn pattern_if_active(&self, subboard: Place) -> Option<Pattern> {
@@ -61,102 +226,135 @@ impl BoardState {
     }
 
     pub fn subboard_pattern(&self) -> Pattern {
-        let pieces = self.board.map(|subboard| {
-            match subboard {
-                Subboard::Won(player) => player.to_piece(),
-                _ => Piece::Empty,
-            }
-        });
-
-        Pattern::new(pieces)
+        Pattern::from_bitboards(self.won_cross, self.won_dot)
     }
 
     pub fn enumerate(&self) -> EnumerateBoard<'_> {
-        EnumerateBoard::new(
-            self.board.iter()
-            .enumerate()
-        )
+        EnumerateBoard::new(self)
+    }
+
+    pub(super) fn decided(&self) -> Bitboard {
+        self.won_cross | self.won_dot | self.drawn
+    }
+
+    pub(super) fn active(&self) -> Bitboard {
+        self.active
+    }
+
+    pub fn piece(&self, spot: Spot) -> Piece {
+        let index = spot.subboard.to_index();
+        if self.crosses[index].contains(spot.square) {
+            Piece::Cross
+        } else if self.dots[index].contains(spot.square) {
+            Piece::Dot
+        } else {
+            Piece::Empty
+        }
     }
     
     pub fn do_move(&self, move_: Move) -> Self {
-        let mut new_subboards = self.board; // Copies board.
-        let subboard = &mut new_subboards[move_.subboard().to_index()];
-        let pattern = match subboard {
-            Subboard::Won(_)      => panic!("invalid move; subboard is won"),
-            Subboard::Inactive(_) => panic!("invalid move; subboard is inactive"),
-            Subboard::Active(pattern) => pattern,
-        };
-        
-        let piece: &mut Piece = pattern.piece_mut(move_.square());
-        let new_piece = self.turn.to_piece();
-        match piece {
-            Piece::Cross => panic!("invalid move; square is non-empty"),
-            Piece::Dot   => panic!("invalid move; square is non-empty"),
-            Piece::Empty => {
-                *piece = new_piece;
-            },
-        };
-        
-        if let PatternState::Won(player) = pattern.state() {
-            *subboard = Subboard::Won(player);
-        } else {
-            *subboard = Subboard::Inactive(*pattern);
+        self.try_do_move(move_)
+            .unwrap_or_else(|error| panic!("invalid move; {}", error))
+    }
+
+    pub fn try_do_move(&self, move_: Move) -> Result<Self, MoveError> {
+        if self.state() != PatternState::Undecided {
+            return Err(MoveError::GameOver);
         }
 
-        let new_active_subboard = &mut new_subboards[move_.square().to_index()];
-        if let Subboard::Inactive(pattern) = new_active_subboard {
-            *new_active_subboard = Subboard::Active(*pattern);
+        let subboard = move_.subboard();
+        let square   = move_.square();
+        if (self.won_cross | self.won_dot).contains(subboard) {
+            return Err(MoveError::SubboardWon);
         }
-        if let Subboard::Won(_) = new_active_subboard {
-            for subboard in &mut new_subboards {
-                if let Subboard::Inactive(pattern) = *subboard {
-                    *subboard = Subboard::Active(pattern);
-                }
-            }
+        if self.drawn.contains(subboard) {
+            // Every square of a drawn subboard is taken.
+            return Err(MoveError::SquareOccupied);
         }
-        
-        let new_turn = self.turn.opposite();
-        BoardState {
-            board: new_subboards,
-            turn: new_turn,
+        if !self.active.contains(subboard) {
+            return Err(MoveError::SubboardInactive);
+        }
+
+        let index = subboard.to_index();
+        if (self.crosses[index] | self.dots[index]).contains(square) {
+            return Err(MoveError::SquareOccupied);
         }
+
+        let mut new_board_state = *self; // Copies board.
+        let (own, won) = match self.turn {
+            Player::Cross => (&mut new_board_state.crosses[index], &mut new_board_state.won_cross),
+            Player::Dot   => (&mut new_board_state.dots   [index], &mut new_board_state.won_dot),
+        };
+        *own = own.with(square);
+
+        if own.has_line() {
+            *won = won.with(subboard);
+        } else if (new_board_state.crosses[index] | new_board_state.dots[index]).is_full() {
+            new_board_state.drawn = new_board_state.drawn.with(subboard);
+        }
+
+        // The player is sent to the subboard matching the square just played.
+        // If that subboard is decided, they may play on any undecided subboard.
+        let decided = new_board_state.decided();
+        new_board_state.active = if decided.contains(square) {
+            !decided
+        } else {
+            Bitboard::from_place(square)
+        };
+
+        new_board_state.turn = self.turn.opposite();
+
+        new_board_state.key ^= zobrist::piece_key(self.turn, subboard, square);
+        new_board_state.key ^= zobrist::active_key(self.active) ^ zobrist::active_key(new_board_state.active);
+        new_board_state.key ^= zobrist::turn_key(self.turn) ^ zobrist::turn_key(new_board_state.turn);
+
+        Ok(new_board_state)
     }
 
     pub fn eligible_moves(&self) -> Box<[Move]> {
-        self
-            .enumerate()
-            .flat_map(|(subboard_place, subboard)| {
-                match subboard.pattern_if_active() {
-                    Some(pattern) => {
-                        pattern.spots(Piece::Empty).iter()
-                            .map(|place| Spot {
-                                subboard: subboard_place,
-                                square: *place,
-                            })
-                            .collect::<Box<[Spot]>>()
-                    },
-                    _ => Box::new([]),
-                }
+        self.active
+            .places()
+            .flat_map(|subboard| {
+                let index = subboard.to_index();
+                let empty = !(self.crosses[index] | self.dots[index]);
+                empty
+                    .places()
+                    .map(move |square| Move::new(Spot {
+                        subboard,
+                        square,
+                    }))
             })
-            .map(Move::new)
             .collect()
     }
 
     pub fn state(&self) -> PatternState {
-        let subboard_pattern = self.subboard_pattern();
-        
-        subboard_pattern.state()
+        if self.won_cross.has_line() {
+            return PatternState::Won(Player::Cross);
+        }
+        if self.won_dot.has_line() {
+            return PatternState::Won(Player::Dot);
+        }
+
+        // Drawn subboards count for no one, so the game is drawn once every
+        // subboard is decided without a line of won subboards.
+        if self.decided().is_full() {
+            return PatternState::Drawn;
+        }
+
+        PatternState::Undecided
     }
 }
 
 pub struct EnumerateBoard<'a> {
-    iter: Enumerate<std::slice::Iter<'a, Subboard>>,
+    board_state: &'a BoardState,
+    index: usize,
 }
 
 impl<'a> EnumerateBoard<'a> {
-    fn new(iter: Enumerate<std::slice::Iter<'a, Subboard>>) -> Self {
+    fn new(board_state: &'a BoardState) -> Self {
         Self {
-            iter,
+            board_state,
+            index: 0,
         }
     }
 }
@@ -165,21 +363,71 @@ impl<'a> Iterator for EnumerateBoard<'a> {
     type Item = (Place, &'a Subboard);
     
     fn next(&mut self) -> Option<Self::Item> {
-        let inner_next = self.iter.next();
+        if self.index == 9 {
+            return None;
+        }
+
+        let place = Place::from_index(self.index);
+        self.index += 1;
 
-        inner_next.map(|inner_next|
-            (Place::from_index(inner_next.0), inner_next.1)
-        )
+        Some((place, interned_subboard(self.board_state, place)))
     }
 }
 
+const UNDECIDED_PATTERNS: usize = 19_683; // 3^9
+
+// Subboards are derived from the bitboards on demand, so `enumerate` lends
+// them from a table of every subboard there is: the decided ones, then the
+// active and the inactive ones, indexed by their pattern in base 3.
+fn interned_subboard(board_state: &BoardState, place: Place) -> &'static Subboard {
+    static SUBBOARDS: OnceLock<Box<[Subboard]>> = OnceLock::new();
+    let subboards = SUBBOARDS.get_or_init(|| {
+        let patterns = || (0..UNDECIDED_PATTERNS).map(|index| {
+            let (mut crosses, mut dots) = (Bitboard::EMPTY, Bitboard::EMPTY);
+            let mut digits = index;
+            for square in 0..9 {
+                match digits % 3 {
+                    1 => crosses = crosses.with(Place::from_index(square)),
+                    2 => dots    = dots   .with(Place::from_index(square)),
+                    _ => (),
+                }
+                digits /= 3;
+            }
+            Pattern::from_bitboards(crosses, dots)
+        });
+        [Subboard::Won(Player::Cross), Subboard::Won(Player::Dot), Subboard::Drawn]
+            .into_iter()
+            .chain(patterns().map(Subboard::Active))
+            .chain(patterns().map(Subboard::Inactive))
+            .collect()
+    });
+
+    let index = match board_state.subboard(place) {
+        Subboard::Won(Player::Cross) => 0,
+        Subboard::Won(Player::Dot)   => 1,
+        Subboard::Drawn              => 2,
+        Subboard::Active(_) | Subboard::Inactive(_) => {
+            let subboard = place.to_index();
+            let digits = |bitboard: Bitboard, digit: usize| {
+                bitboard.places().map(|square| digit * 3_usize.pow(square.to_index() as u32)).sum::<usize>()
+            };
+            let pattern = digits(board_state.crosses[subboard], 1) + digits(board_state.dots[subboard], 2);
+            match board_state.active.contains(place) {
+                true  => 3 + pattern,
+                false => 3 + UNDECIDED_PATTERNS + pattern,
+            }
+        },
+    };
+    &subboards[index]
+}
+
 #[cfg(test)]
 mod tests {
     use std::panic;
 
-    use crate::utils::{pattern::Pattern, raw::{RawActiveSubBoard, RawPiece, RawTurn}, Move, Place, Player, RawBoardState, Spot, Subboard};
+    use crate::utils::{pattern::{Pattern, PatternState}, raw::{RawActiveSubBoard, RawPiece, RawTurn}, FfiBoardState, Move, Place, Player, RawBoardState, Spot, Subboard};
 
-    use super::BoardState;
+    use super::{BoardState, InvalidBoardState, MoveError};
 
     const TEST_BOARD: [[&str; 3]; 9] = [
         [
@@ -300,11 +548,66 @@ mod tests {
                     } else {
                         panic!();
                     }
-                }
+                },
+                Subboard::Drawn => panic!(),
             }
         }
     }
     
+    #[test]
+    fn from_ffi() {
+        let board_state = BoardState::new_empty(Player::Cross)
+            .do_move(Move::new(Spot { subboard: Place::MidMid, square: Place::TopLef }))
+            .do_move(Move::new(Spot { subboard: Place::TopLef, square: Place::MidMid }));
+        let ffi_board_state = FfiBoardState::from(board_state.to_raw());
+        assert_eq!(ffi_board_state.turn, 1);
+        assert_eq!(ffi_board_state.current, 4);
+        assert_eq!(ffi_board_state.board[0][4], -1);
+        assert_eq!(BoardState::from_ffi(ffi_board_state), Ok(board_state));
+
+        let mut invalid = ffi_board_state;
+        invalid.board[2][3] = 2;
+        assert_eq!(BoardState::from_ffi(invalid), Err(InvalidBoardState::InvalidPiece { subboard: 2, square: 3, value: 2 }));
+        let mut invalid = ffi_board_state;
+        invalid.turn = 0;
+        assert_eq!(BoardState::from_ffi(invalid), Err(InvalidBoardState::InvalidTurn(0)));
+        let mut invalid = ffi_board_state;
+        invalid.current = 9;
+        assert_eq!(BoardState::from_ffi(invalid), Err(InvalidBoardState::InvalidActiveSubboard(9)));
+        // Either player may have moved first.
+        let mut valid = ffi_board_state;
+        valid.turn = -1;
+        assert!(BoardState::from_ffi(valid).is_ok());
+        let mut invalid = ffi_board_state;
+        invalid.board[8][0] = 1;
+        invalid.board[8][1] = 1;
+        assert_eq!(BoardState::from_ffi(invalid), Err(InvalidBoardState::PieceCounts { crosses: 3, dots: 1, turn: Player::Cross }));
+
+        // Dot moves first here, with cross having won the active subboard.
+        let mut invalid = FfiBoardState::from(BoardState::new_empty(Player::Dot).to_raw());
+        invalid.board[4] = [1, 1, 1, -1, -1, 0, -1, 0, 0];
+        invalid.current = 4;
+        assert_eq!(BoardState::from_ffi(invalid), Err(InvalidBoardState::ActiveSubboardDecided(4)));
+        invalid.current = -1;
+        assert!(BoardState::from_ffi(invalid).is_ok());
+    }
+
+    #[test]
+    fn to_raw() {
+        let raw_board_state = RawBoardState {
+            active_subboard: RawActiveSubBoard::MidMid,
+            turn: RawTurn::Dot,
+            board: test_board(),
+        };
+        assert_eq!(BoardState::from_raw(raw_board_state).to_raw(), raw_board_state);
+
+        let raw_board_state = RawBoardState {
+            active_subboard: RawActiveSubBoard::All,
+            ..raw_board_state
+        };
+        assert_eq!(BoardState::from_raw(raw_board_state).to_raw(), raw_board_state);
+    }
+
     #[test]
     fn pattern_if_active() {
         let raw_board_state = RawBoardState {
@@ -351,6 +654,21 @@ mod tests {
         assert_eq!(pattern, board_state.subboard_pattern());
     }
 
+    #[test]
+    fn enumerate_derives_subboards() {
+        // Lent subboards match the ones derived one by one, through a game
+        // with won, drawn, active and inactive subboards.
+        let mut board_state = BoardState::new_empty(Player::Cross);
+        let mut index = 0;
+        while board_state.state() == PatternState::Undecided {
+            assert!(board_state.enumerate().all(|(place, subboard)| *subboard == board_state.subboard(place)));
+            let moves = board_state.eligible_moves();
+            index = (index * 7 + 3) % moves.len();
+            board_state = board_state.do_move(moves[index]);
+        }
+        assert!(board_state.enumerate().all(|(place, subboard)| *subboard == board_state.subboard(place)));
+    }
+
     #[test]
     fn enumerate() {
         let raw_board_state = RawBoardState {
@@ -437,6 +755,62 @@ mod tests {
             });
     }
 
+    #[test]
+    fn try_do_move() {
+        let raw_board_state = RawBoardState {
+            active_subboard: RawActiveSubBoard::MidMid,
+            turn: RawTurn::Cross,
+            board: test_board(),
+        };
+        
+        let board_state = BoardState::from_raw(raw_board_state);
+
+        let move_ = Move::new(Spot {
+            subboard: Place::BotLef,
+            square: Place::TopLef,
+        });
+        assert_eq!(board_state.try_do_move(move_), Err(MoveError::SubboardWon));
+
+        let move_ = Move::new(Spot {
+            subboard: Place::MidLef,
+            square: Place::BotMid,
+        });
+        assert_eq!(board_state.try_do_move(move_), Err(MoveError::SubboardInactive));
+
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::MidRig,
+        });
+        assert_eq!(board_state.try_do_move(move_), Err(MoveError::SquareOccupied));
+
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::TopLef,
+        });
+        assert_eq!(board_state.try_do_move(move_), Ok(board_state.do_move(move_)));
+
+        let game_over = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X X",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], -1, "dot",
+        );
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::MidMid,
+        });
+        assert_eq!(game_over.try_do_move(move_), Err(MoveError::GameOver));
+    }
+
     #[test]
     fn eligible_moves() {
         let raw_board_state = RawBoardState {
@@ -456,4 +830,162 @@ mod tests {
 
         assert_eq!(*board_state.eligible_moves(), *eligible_moves);
     }
+
+    #[test]
+    fn do_move_sent_to_decided() {
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "  O X.     .     ",
+                "X O O.     .     ",
+                "O X O.     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], 0, "cross",
+        );
+
+        let move_ = Move::new(Spot {
+            subboard: Place::TopLef,
+            square: Place::TopLef,
+        });
+        let new_board_state = board_state.do_move(move_);
+        assert_eq!(new_board_state.subboard(Place::TopLef), Subboard::Drawn);
+        new_board_state
+            .enumerate()
+            .for_each(|(place, subboard)| {
+                if place == Place::TopLef {
+                    return;
+                }
+                assert!(matches!(subboard, Subboard::Active(_)));
+            });
+
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::TopLef,
+        });
+        let new_board_state = new_board_state.do_move(move_);
+        assert_eq!(new_board_state.eligible_moves().len(), 8 * 9 - 1);
+
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::TopMid,
+        });
+        let new_board_state = new_board_state.do_move(move_);
+        new_board_state
+            .enumerate()
+            .for_each(|(place, subboard)| {
+                match place {
+                    Place::TopLef => assert_eq!(*subboard, Subboard::Drawn),
+                    Place::TopMid => assert!(matches!(subboard, Subboard::Active(_))),
+                    _             => assert!(matches!(subboard, Subboard::Inactive(_))),
+                }
+            });
+    }
+
+    #[test]
+    fn state() {
+        let undecided = BoardState::dbg_from_matrix(
+            [
+                "X O X.X X X.O O O",
+                "X O O.     .     ",
+                "O X O.     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], -1, "cross",
+        );
+        assert_eq!(undecided.state(), PatternState::Undecided);
+
+        let won = BoardState::dbg_from_matrix(
+            [
+                "X X X.X X X.X X X",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+
+                "     .     .     ",
+                "     .     .     ",
+                "     .     .     ",
+            ], -1, "dot",
+        );
+        assert_eq!(won.state(), PatternState::Won(Player::Cross));
+
+        let drawn = BoardState::dbg_from_matrix(
+            [
+                "X O X.X X X.O O O",
+                "X O O.     .     ",
+                "O X O.     .     ",
+
+                "O O O.X O X.X X X",
+                "     .X O O.     ",
+                "     .O X O.     ",
+
+                "X X X.O O O.X O X",
+                "     .     .X O O",
+                "     .     .O X O",
+            ], -1, "dot",
+        );
+        assert_eq!(drawn.state(), PatternState::Drawn);
+        assert!(drawn.eligible_moves().is_empty());
+    }
+
+    #[test]
+    fn zobrist_key() {
+        let moves = [
+            (Place::MidMid, Place::TopLef),
+            (Place::TopLef, Place::MidMid),
+            (Place::MidMid, Place::MidMid),
+            (Place::MidMid, Place::TopMid),
+            (Place::TopMid, Place::MidMid),
+            (Place::MidMid, Place::BotRig),
+        ];
+        let mut board_state = BoardState::new_empty(Player::Cross);
+        let mut keys = vec![board_state.zobrist_key()];
+        for (subboard, square) in moves {
+            board_state = board_state.do_move(Move::new(Spot { subboard, square }));
+            assert_eq!(board_state.zobrist_key(), board_state.compute_key());
+            keys.push(board_state.zobrist_key());
+        }
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), moves.len() + 1);
+
+        let raw_board_state = RawBoardState {
+            active_subboard: RawActiveSubBoard::MidMid,
+            turn: RawTurn::Cross,
+            board: test_board(),
+        };
+        let board_state = BoardState::from_raw(raw_board_state);
+        let move_ = Move::new(Spot {
+            subboard: Place::MidMid,
+            square: Place::TopLef,
+        });
+        let new_board_state = board_state.do_move(move_);
+        assert_eq!(new_board_state.zobrist_key(), new_board_state.compute_key());
+
+        // Same pieces with a different turn or active subboard hash differently.
+        let raw_board_state = RawBoardState {
+            turn: RawTurn::Dot,
+            ..raw_board_state
+        };
+        assert_ne!(BoardState::from_raw(raw_board_state).zobrist_key(), board_state.zobrist_key());
+        let raw_board_state = RawBoardState {
+            active_subboard: RawActiveSubBoard::All,
+            ..raw_board_state
+        };
+        assert_ne!(BoardState::from_raw(raw_board_state).zobrist_key(), board_state.zobrist_key());
+    }
 }
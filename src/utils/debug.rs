@@ -1,4 +1,4 @@
-use crate::utils::{Place, Player, Subboard};
+use crate::utils::{Place, Player, Spot, Subboard};
 
 use super::{board_state::BoardState, pattern::Pattern, raw::{RawActiveSubBoard, RawPiece, RawTurn}, Move, Piece, RawBoardState};
 
@@ -47,7 +47,12 @@ impl BoardState {
         };
 
         eprintln!("Turn: {}", turn);
+        eprint!("{}", self.dbg_render(|_, character| character.to_owned()));
+    }
 
+    // The board as printed by `dbg_print`, with each square's character passed
+    // through `paint`, e.g. to add terminal colours.
+    pub fn dbg_render(&self, paint: impl Fn(Spot, &str) -> String) -> String {
         let active_subboards: Vec<_> = (0..9)
             .map(|index| {
                 let subboard = self.subboard(Place::from_index(index));
@@ -70,17 +75,25 @@ impl BoardState {
                         }
                     }
                 },
+                Subboard::Drawn => {
+                    for i in 0..3 {
+                        for j in 0..3 {
+                            rows[(subboard_index / 3) * 3 + i][(subboard_index % 3) * 3 + j] = String::from("#");
+                        }
+                    }
+                },
                 Subboard::Active  (pattern) => Self::dbg_add_pattern(pattern, &mut rows, subboard_index),
                 Subboard::Inactive(pattern) => Self::dbg_add_pattern(pattern, &mut rows, subboard_index),
             }
         }
         
+        let mut rendered = String::new();
         rows
             .iter()
             .enumerate()
             .for_each(|(i, row)| {
                 if i == 3 || i == 6 {
-                    eprintln!("------+------+------");
+                    rendered.push_str("------+------+------\n");
                 }
 
                 row
@@ -88,20 +101,25 @@ impl BoardState {
                     .enumerate()
                     .for_each(|(j, character)| {
                         let subboard = j / 3 + (i / 3) * 3;
+                        let spot = Spot {
+                            subboard: Place::from_index(subboard),
+                            square:   Place::from_index((i % 3) * 3 + j % 3),
+                        };
 
                         if active_subboards[subboard] && character == " " {
-                            eprint!("+");
+                            rendered.push_str(&paint(spot, "+"));
                         } else {
-                            eprint!("{}", character);
+                            rendered.push_str(&paint(spot, character));
                         }
 
-                        eprint!(" ");
+                        rendered.push(' ');
                         if j == 2 || j == 5 {
-                            eprint!("|");
+                            rendered.push('|');
                         }
                     });
-                    eprintln!();
+                    rendered.push('\n');
             });
+        rendered
     }
 
     fn dbg_add_pattern(pattern: Pattern, rows: &mut [Vec<String>], subboard_index: usize) {
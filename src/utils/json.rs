@@ -0,0 +1,263 @@
+use std::{error::Error, fmt::Display};
+
+// Just enough JSON for settings files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String), // As written, so integers too large for an `f64` stay exact.
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    UnexpectedEnd,
+    UnexpectedCharacter(usize), // Byte offset into the text.
+    InvalidNumber(usize),
+    InvalidEscape(usize),
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEnd                 => write!(f, "unexpected end of json"),
+            Self::UnexpectedCharacter(position) => write!(f, "unexpected character in json at {}", position),
+            Self::InvalidNumber(position)       => write!(f, "invalid number in json at {}", position),
+            Self::InvalidEscape(position)       => write!(f, "invalid escape in json at {}", position),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            position: 0,
+        };
+        let json = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.text.len() {
+            return Err(JsonError::UnexpectedCharacter(parser.position));
+        }
+        Ok(json)
+    }
+
+    // The value of `key`, if this is an object containing it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // Only integers written without a fraction or exponent.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(bool) => Some(*bool),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text:     &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Result<u8, JsonError> {
+        self.skip_whitespace();
+        self.text.get(self.position).copied().ok_or(JsonError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.peek()? != byte {
+            return Err(JsonError::UnexpectedCharacter(self.position));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, json: Json) -> Result<Json, JsonError> {
+        if !self.text[self.position..].starts_with(literal.as_bytes()) {
+            return Err(JsonError::UnexpectedCharacter(self.position));
+        }
+        self.position += literal.len();
+        Ok(json)
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        match self.peek()? {
+            b'n' => self.literal("null", Json::Null),
+            b't' => self.literal("true", Json::Bool(true)),
+            b'f' => self.literal("false", Json::Bool(false)),
+            b'"' => self.string().map(Json::String),
+            b'[' => self.array(),
+            b'{' => self.object(),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(JsonError::UnexpectedCharacter(self.position)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        while self.text.get(self.position).is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.position += 1;
+        }
+        // The slice only holds ASCII characters.
+        let number = std::str::from_utf8(&self.text[start..self.position]).expect("ascii is valid utf-8");
+        match number.parse::<f64>() {
+            Ok(_)  => Ok(Json::Number(number.to_owned())),
+            Err(_) => Err(JsonError::InvalidNumber(start)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&byte) = self.text.get(self.position) else {
+                return Err(JsonError::UnexpectedEnd);
+            };
+            self.position += 1;
+            match byte {
+                b'"'  => break,
+                b'\\' => {
+                    let Some(&escaped) = self.text.get(self.position) else {
+                        return Err(JsonError::UnexpectedEnd);
+                    };
+                    let unescaped = match escaped {
+                        b'"'  => b'"',
+                        b'\\' => b'\\',
+                        b'/'  => b'/',
+                        b'n'  => b'\n',
+                        b't'  => b'\t',
+                        b'r'  => b'\r',
+                        _     => return Err(JsonError::InvalidEscape(self.position)),
+                    };
+                    self.position += 1;
+                    bytes.push(unescaped);
+                },
+                _ => bytes.push(byte),
+            }
+        }
+        // Only whole characters of the valid utf-8 input are copied.
+        Ok(String::from_utf8(bytes).expect("copied from valid utf-8"))
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.peek()? == b']' {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek()? {
+                b',' => self.position += 1,
+                b']' => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                },
+                _ => return Err(JsonError::UnexpectedCharacter(self.position)),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        if self.peek()? == b'}' {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value()?));
+            match self.peek()? {
+                b',' => self.position += 1,
+                b'}' => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                },
+                _ => return Err(JsonError::UnexpectedCharacter(self.position)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, JsonError};
+
+    #[test]
+    fn parse() {
+        let json = Json::parse(r#"
+            {
+              "timeout": 3,
+              "seed": -1.5e1,
+              "path": "AI/\"bot\"/",
+              "flags": [true, false, null],
+              "nested": {}
+            }
+        "#).unwrap();
+        assert_eq!(json.get("timeout"), Some(&Json::Number("3".to_owned())));
+        assert_eq!(json.get("timeout").and_then(Json::as_u64), Some(3));
+        assert_eq!(json.get("seed").and_then(Json::as_f64), Some(-15.0));
+        assert_eq!(json.get("seed").and_then(Json::as_u64), None);
+        assert_eq!(Json::parse("18446744073709551615").unwrap().as_u64(), Some(u64::MAX));
+        assert_eq!(json.get("path").and_then(Json::as_str), Some("AI/\"bot\"/"));
+        assert_eq!(json.get("flags"), Some(&Json::Array(vec![Json::Bool(true), Json::Bool(false), Json::Null])));
+        assert_eq!(json.get("nested"), Some(&Json::Object(Vec::new())));
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(Json::parse(" [] ").unwrap(), Json::Array(Vec::new()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Json::parse(""), Err(JsonError::UnexpectedEnd));
+        assert_eq!(Json::parse("{\"a\": 1"), Err(JsonError::UnexpectedEnd));
+        assert_eq!(Json::parse("{\"a\" 1}"), Err(JsonError::UnexpectedCharacter(5)));
+        assert_eq!(Json::parse("[1, 2] 3"), Err(JsonError::UnexpectedCharacter(7)));
+        assert_eq!(Json::parse("1.2.3"), Err(JsonError::InvalidNumber(0)));
+        assert_eq!(Json::parse("\"\\x\""), Err(JsonError::InvalidEscape(2)));
+        assert_eq!(Json::parse("nul"), Err(JsonError::UnexpectedCharacter(0)));
+    }
+}
@@ -0,0 +1,379 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use super::{bitboard::Bitboard, board_state::BoardState, pattern::Pattern, Move, Piece, Place, Player, Spot};
+
+// Positions are written on one line, like "9/9/9/9/4X4/9/9/9/9 O 4":
+// * The 9 rows of the whole board from top to bottom, separated by '/'. Each
+//   row lists its squares from left to right as 'X' for cross, 'O' for dot and
+//   digits for runs of empty squares.
+// * The player to move, 'X' or 'O'.
+// * The subboard that must be played on, numbered 0 to 8 from the top left,
+//   or '-' when any undecided subboard may be played on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePositionError {
+    WrongFieldCount,
+    WrongRowCount,
+    WrongRowLength(usize), // Rows are numbered from 0 at the top.
+    InvalidCharacter(char),
+    InvalidTurn(String),
+    InvalidActiveSubboard(String),
+    ActiveSubboardDecided(usize),
+}
+
+impl fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongFieldCount               => write!(f, "expected board, turn and active subboard"),
+            Self::WrongRowCount                 => write!(f, "expected 9 rows"),
+            Self::WrongRowLength(row)           => write!(f, "row {} does not have 9 squares", row),
+            Self::InvalidCharacter(character)   => write!(f, "invalid character '{}' in board", character),
+            Self::InvalidTurn(turn)             => write!(f, "invalid turn \"{}\"", turn),
+            Self::InvalidActiveSubboard(active) => write!(f, "invalid active subboard \"{}\"", active),
+            Self::ActiveSubboardDecided(index)  => write!(f, "active subboard {} is decided", index),
+        }
+    }
+}
+
+impl Error for ParsePositionError {}
+
+// The spot at `column` and `row` of the whole board, counted from the top left.
+fn grid_spot(row: usize, column: usize) -> Spot {
+    Spot {
+        subboard: Place::from_index(row / 3 * 3 + column / 3),
+        square:   Place::from_index(row % 3 * 3 + column % 3),
+    }
+}
+
+// The row and column of `spot` on the whole board, counted from the top left.
+fn grid_position(spot: Spot) -> (usize, usize) {
+    let subboard = spot.subboard.to_index();
+    let square = spot.square.to_index();
+    (subboard / 3 * 3 + square / 3, subboard % 3 * 3 + square % 3)
+}
+
+fn move_spot(move_: Move) -> Spot {
+    Spot {
+        subboard: move_.subboard(),
+        square:   move_.square(),
+    }
+}
+
+// Global indices number the 81 squares of the whole board row by row, from 0
+// at the top left to 80 at the bottom right.
+impl Spot {
+    pub fn from_global_index(index: usize) -> Self {
+        if index >= 81 {
+            panic!("invalid global index");
+        }
+        grid_spot(index / 9, index % 9)
+    }
+
+    pub fn global_index(self) -> usize {
+        let (row, column) = grid_position(self);
+        row * 9 + column
+    }
+}
+
+impl Move {
+    pub fn from_global_index(index: usize) -> Self {
+        Move::new(Spot::from_global_index(index))
+    }
+
+    pub fn global_index(self) -> usize {
+        move_spot(self).global_index()
+    }
+}
+
+// Moves and spots are written like chess squares on the whole board, with files
+// 'a' to 'i' from left to right and ranks 1 to 9 from bottom to top, so "e5" is
+// the center. They are also read as "<subboard>/<square>", like "4/4", with
+// places written as digits 0 to 8 from the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoveError {
+    InvalidPlace(String),
+    InvalidSpot(String),
+}
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPlace(place) => write!(f, "invalid place \"{}\"", place),
+            Self::InvalidSpot(spot)   => write!(f, "invalid move \"{}\"", spot),
+        }
+    }
+}
+
+impl Error for ParseMoveError {}
+
+impl FromStr for Place {
+    type Err = ParseMoveError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.parse::<usize>() {
+            Ok(index) if index < 9 && string.len() == 1 => Ok(Place::from_index(index)),
+            _ => Err(ParseMoveError::InvalidPlace(string.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_index())
+    }
+}
+
+impl FromStr for Spot {
+    type Err = ParseMoveError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if let Some((subboard, square)) = string.split_once('/') {
+            let invalid = |_| ParseMoveError::InvalidSpot(string.to_owned());
+            return Ok(Spot {
+                subboard: subboard.parse().map_err(invalid)?,
+                square:   square.parse().map_err(invalid)?,
+            });
+        }
+
+        let &[file, rank] = string.as_bytes() else {
+            return Err(ParseMoveError::InvalidSpot(string.to_owned()));
+        };
+        if !(b'a'..=b'i').contains(&file) || !(b'1'..=b'9').contains(&rank) {
+            return Err(ParseMoveError::InvalidSpot(string.to_owned()));
+        }
+        let column = (file - b'a') as usize;
+        let row = 8 - (rank - b'1') as usize;
+        Ok(grid_spot(row, column))
+    }
+}
+
+impl fmt::Display for Spot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (row, column) = grid_position(*self);
+        write!(f, "{}{}", (b'a' + column as u8) as char, 9 - row)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        string.parse().map(Move::new)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        move_spot(*self).fmt(f)
+    }
+}
+
+impl FromStr for BoardState {
+    type Err = ParsePositionError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = string.split_whitespace().collect();
+        let [board, turn, active] = fields[..] else {
+            return Err(ParsePositionError::WrongFieldCount);
+        };
+
+        let rows: Vec<_> = board.split('/').collect();
+        if rows.len() != 9 {
+            return Err(ParsePositionError::WrongRowCount);
+        }
+        let mut patterns = [Pattern::from_bitboards(Bitboard::EMPTY, Bitboard::EMPTY); 9];
+        for (row, row_string) in rows.iter().enumerate() {
+            let mut column = 0;
+            for character in row_string.chars() {
+                let (piece, count) = match character {
+                    'X'        => (Piece::Cross, 1),
+                    'O'        => (Piece::Dot,   1),
+                    '1'..='9' => (Piece::Empty, character as usize - '0' as usize),
+                    _          => return Err(ParsePositionError::InvalidCharacter(character)),
+                };
+                if column + count > 9 {
+                    return Err(ParsePositionError::WrongRowLength(row));
+                }
+                for column in column..column + count {
+                    let spot = grid_spot(row, column);
+                    *patterns[spot.subboard.to_index()].piece_mut(spot.square) = piece;
+                }
+                column += count;
+            }
+            if column != 9 {
+                return Err(ParsePositionError::WrongRowLength(row));
+            }
+        }
+
+        let turn = match turn {
+            "X" => Player::Cross,
+            "O" => Player::Dot,
+            _   => return Err(ParsePositionError::InvalidTurn(turn.to_owned())),
+        };
+
+        let active_subboard = match active {
+            "-" => None,
+            _   => match active.parse::<usize>() {
+                Ok(index) if index < 9 => Some(index),
+                _                      => return Err(ParsePositionError::InvalidActiveSubboard(active.to_owned())),
+            },
+        };
+        let board_state = BoardState::from_patterns(
+            patterns,
+            turn,
+            active_subboard.map_or(Bitboard::FULL, |index| Bitboard::from_place(Place::from_index(index))),
+        );
+        if let Some(index) = active_subboard && board_state.decided().contains(Place::from_index(index)) {
+            return Err(ParsePositionError::ActiveSubboardDecided(index));
+        }
+        Ok(board_state)
+    }
+}
+
+impl fmt::Display for BoardState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..9 {
+            if row > 0 {
+                write!(f, "/")?;
+            }
+            let mut empty_run = 0;
+            for column in 0..9 {
+                let character = match self.piece(grid_spot(row, column)) {
+                    Piece::Cross => 'X',
+                    Piece::Dot   => 'O',
+                    Piece::Empty => {
+                        empty_run += 1;
+                        continue;
+                    },
+                };
+                if empty_run > 0 {
+                    write!(f, "{}", empty_run)?;
+                    empty_run = 0;
+                }
+                write!(f, "{}", character)?;
+            }
+            if empty_run > 0 {
+                write!(f, "{}", empty_run)?;
+            }
+        }
+
+        let turn = match self.turn() {
+            Player::Cross => 'X',
+            Player::Dot   => 'O',
+        };
+        write!(f, " {} ", turn)?;
+
+        // Several active subboards can only be all undecided ones.
+        let mut active_places = self.active().places();
+        match (active_places.next(), active_places.next()) {
+            (Some(place), None) => write!(f, "{}", place.to_index()),
+            _                   => write!(f, "-"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{board_state::BoardState, Move, Place, Player, Spot};
+
+    use super::{ParseMoveError, ParsePositionError};
+
+    const START: &str = "9/9/9/9/9/9/9/9/9 X -";
+
+    #[test]
+    fn start() {
+        let board_state: BoardState = START.parse().unwrap();
+        assert_eq!(board_state, BoardState::new_empty(Player::Cross));
+        assert_eq!(board_state.to_string(), START);
+    }
+
+    #[test]
+    fn moves() {
+        let board_state = BoardState::new_empty(Player::Cross)
+            .do_move(Move::new(Spot { subboard: Place::MidMid, square: Place::TopLef }))
+            .do_move(Move::new(Spot { subboard: Place::TopLef, square: Place::BotRig }));
+        assert_eq!(board_state.to_string(), "9/9/2O6/3X5/9/9/9/9/9 X 8");
+        assert_eq!("9/9/2O6/3X5/9/9/9/9/9 X 8".parse(), Ok(board_state));
+    }
+
+    #[test]
+    fn round_trip() {
+        let board_state = BoardState::dbg_from_matrix(
+            [
+                "X X X.O    .     ",
+                "  O  .  O  .  X  ",
+                "     .     .     ",
+
+                "     .X O X.     ",
+                "     .X O O.     ",
+                "     .O X X.  O  ",
+
+                "O    .     .     ",
+                "  O  .     .     ",
+                "    O.     .    X",
+            ], -1, "dot",
+        );
+        let string = board_state.to_string();
+        assert_eq!(string, "XXXO5/1O2O2X1/9/3XOX3/3XOO3/3OXX1O1/O8/1O7/2O5X O -");
+        assert_eq!(string.parse(), Ok(board_state));
+
+        let board_state = board_state.do_move(Move::new(Spot { subboard: Place::TopRig, square: Place::BotRig }));
+        assert_eq!(board_state.to_string().parse(), Ok(board_state));
+        assert!(board_state.to_string().ends_with(" X 8"));
+
+        // Sent to the won subboard 0.
+        let board_state = board_state.do_move(Move::new(Spot { subboard: Place::BotRig, square: Place::TopLef }));
+        assert_eq!(board_state.to_string().parse(), Ok(board_state));
+        assert!(board_state.to_string().ends_with(" O -"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |string: &str| string.parse::<BoardState>().err();
+        assert_eq!(parse("9/9/9/9/9/9/9/9/9 X"), Some(ParsePositionError::WrongFieldCount));
+        assert_eq!(parse("9/9/9/9/9/9/9/9 X -"), Some(ParsePositionError::WrongRowCount));
+        assert_eq!(parse("9/9/9/9/9/9/9/8/9 X -"), Some(ParsePositionError::WrongRowLength(7)));
+        assert_eq!(parse("9/9/9/9/9/9/9/9/X9 X -"), Some(ParsePositionError::WrongRowLength(8)));
+        assert_eq!(parse("9/9/9/9/9/9/9/9/4.4 X -"), Some(ParsePositionError::InvalidCharacter('.')));
+        assert_eq!(parse("9/9/9/9/9/9/9/9/9 x -"), Some(ParsePositionError::InvalidTurn("x".to_owned())));
+        assert_eq!(parse("9/9/9/9/9/9/9/9/9 X 9"), Some(ParsePositionError::InvalidActiveSubboard("9".to_owned())));
+        assert_eq!(parse("XXX6/9/9/9/9/9/9/9/9 O 0"), Some(ParsePositionError::ActiveSubboardDecided(0)));
+    }
+
+    #[test]
+    fn move_notation() {
+        let move_ = Move::new(Spot { subboard: Place::MidMid, square: Place::MidMid });
+        assert_eq!(move_.to_string(), "e5");
+        assert_eq!("e5".parse(), Ok(move_));
+        assert_eq!("4/4".parse(), Ok(move_));
+
+        let move_ = Move::new(Spot { subboard: Place::TopRig, square: Place::BotLef });
+        assert_eq!(move_.to_string(), "g7");
+        assert_eq!("2/6".parse(), Ok(move_));
+        assert_eq!(Move::new(Spot { subboard: Place::BotLef, square: Place::BotLef }).to_string(), "a1");
+        assert_eq!(Move::new(Spot { subboard: Place::TopRig, square: Place::TopRig }).to_string(), "i9");
+
+        for index in 0..81 {
+            let move_ = Move::from_global_index(index);
+            assert_eq!(move_.global_index(), index);
+            assert_eq!(move_.to_string().parse(), Ok(move_));
+        }
+        assert_eq!(Spot::from_global_index(0), Spot { subboard: Place::TopLef, square: Place::TopLef });
+        assert_eq!(Spot::from_global_index(12), Spot { subboard: Place::TopMid, square: Place::MidLef });
+        assert_eq!(Spot::from_global_index(80), Spot { subboard: Place::BotRig, square: Place::BotRig });
+    }
+
+    #[test]
+    fn place_notation() {
+        assert_eq!(Place::BotMid.to_string(), "7");
+        assert_eq!("7".parse(), Ok(Place::BotMid));
+        assert_eq!("9".parse::<Place>(), Err(ParseMoveError::InvalidPlace("9".to_owned())));
+        assert_eq!("07".parse::<Place>(), Err(ParseMoveError::InvalidPlace("07".to_owned())));
+    }
+
+    #[test]
+    fn move_parse_errors() {
+        for string in ["", "e", "e0", "j5", "E5", "e55", "4/9", "4/", "/4", "4/4/4"] {
+            assert_eq!(string.parse::<Move>(), Err(ParseMoveError::InvalidSpot(string.to_owned())));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::iter::Enumerate;
 
+use super::bitboard::Bitboard;
 use super::raw::RawPiece;
 use super::Piece;
 use super::Place;
@@ -11,6 +12,7 @@ pub struct Pattern([Piece; 9]);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PatternState {
     Won(Player),
+    Drawn,
     Undecided,
 }
 
@@ -117,6 +119,35 @@ impl Pattern {
         }))
     }
 
+    pub(super) fn to_raw(self) -> [RawPiece; 9] {
+        self.0.map(|piece| {
+            match piece {
+                Piece::Cross => RawPiece::Cross,
+                Piece::Dot   => RawPiece::Dot,
+                Piece::Empty => RawPiece::Empty,
+            }
+        })
+    }
+
+    pub fn from_bitboards(crosses: Bitboard, dots: Bitboard) -> Self {
+        Pattern(std::array::from_fn(|index| {
+            let place = Place::from_index(index);
+            if crosses.contains(place) {
+                Piece::Cross
+            } else if dots.contains(place) {
+                Piece::Dot
+            } else {
+                Piece::Empty
+            }
+        }))
+    }
+
+    pub fn bitboard(&self, piece: Piece) -> Bitboard {
+        self.enumerate()
+            .filter(|(_, other_piece)| **other_piece == piece)
+            .fold(Bitboard::EMPTY, |bitboard, (place, _)| bitboard.with(place))
+    }
+
     pub fn piece(&self, place: Place) -> &Piece {
         &self.0[place.to_index()]
     }
@@ -143,26 +174,20 @@ impl Pattern {
     }
     
     pub fn state(&self) -> PatternState {
-        let cross_won = Pattern::WINNING_PATTERNS_CROSS
-            .iter()
-            .any(|pattern| {
-                self.contains(*pattern)
-            });
-
-        if cross_won {
+        let crosses = self.bitboard(Cross);
+        if crosses.has_line() {
             return PatternState::Won(Player::Cross);
         }
 
-        let dot_won = Pattern::WINNING_PATTERNS_DOT
-            .iter()
-            .any(|pattern| {
-                self.contains(*pattern)
-            });
-
-        if dot_won {
+        let dots = self.bitboard(Dot);
+        if dots.has_line() {
             return PatternState::Won(Player::Dot);
         }
 
+        if (crosses | dots).is_full() {
+            return PatternState::Drawn;
+        }
+
         PatternState::Undecided
     }
 
@@ -243,6 +268,23 @@ mod tests {
         assert_eq!(*pattern.piece_mut(Place::MidMid), Piece::Empty);
     }
 
+    #[test]
+    fn from_bitboards_bitboard() {
+        let pattern = Pattern::dbg_from_matrix([
+            "X    ",
+            "  X O",
+            "X O O",
+        ]);
+        let crosses = pattern.bitboard(Piece::Cross);
+        let dots    = pattern.bitboard(Piece::Dot);
+        assert_eq!(crosses.count(), 3);
+        assert_eq!(dots.count(),    3);
+        assert!(crosses.contains(Place::MidMid));
+        assert!(dots.contains(Place::BotRig));
+        assert_eq!(pattern.bitboard(Piece::Empty), !(crosses | dots));
+        assert_eq!(Pattern::from_bitboards(crosses, dots), pattern);
+    }
+
     #[test]
     fn enumerate() {
         let pattern = Pattern::dbg_from_matrix([
@@ -310,12 +352,24 @@ mod tests {
             "X   X",
         ]);
         assert_eq!(won_dot.state(),   PatternState::Won(Player::Dot));
-        let undecided = Pattern::dbg_from_matrix([
+        let drawn = Pattern::dbg_from_matrix([
             "X O X",
             "X O O",
             "O X O",
         ]);
+        assert_eq!(drawn.state(), PatternState::Drawn);
+        let undecided = Pattern::dbg_from_matrix([
+            "X O X",
+            "X O O",
+            "O X  ",
+        ]);
         assert_eq!(undecided.state(), PatternState::Undecided);
+        let won_full = Pattern::dbg_from_matrix([
+            "X O X",
+            "O X O",
+            "O X X",
+        ]);
+        assert_eq!(won_full.state(), PatternState::Won(Player::Cross));
     }
 
     #[test]
@@ -1,5 +1,7 @@
 use std::convert::Infallible;
 
+use super::board_state::InvalidBoardState;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RawBoardState {
@@ -8,6 +10,16 @@ pub struct RawBoardState {
     pub active_subboard: RawActiveSubBoard,
 }
 
+// The board state as the C++ interface sends it, as plain integers: any value
+// may arrive, so it has to be validated before becoming a `RawBoardState`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiBoardState {
+    pub board:   [[i32; 9]; 9], // 1 for cross, 0 for empty, -1 for dot.
+    pub turn:    i32,           // 1 for cross, -1 for dot.
+    pub current: i32,           // The active subboard, or -1 for all.
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RawMove {
@@ -63,3 +75,58 @@ pub enum RawTurn {
     Empty(Infallible) =  0, // On the cpp side, Piece is used as Turn too
     Dot               = -1,
 }
+
+impl TryFrom<FfiBoardState> for RawBoardState {
+    type Error = InvalidBoardState;
+
+    fn try_from(ffi_board_state: FfiBoardState) -> Result<Self, Self::Error> {
+        let mut board = [[RawPiece::Empty; 9]; 9];
+        for (subboard, pattern) in ffi_board_state.board.iter().enumerate() {
+            for (square, &piece) in pattern.iter().enumerate() {
+                board[subboard][square] = match piece {
+                     1 => RawPiece::Cross,
+                     0 => RawPiece::Empty,
+                    -1 => RawPiece::Dot,
+                     _ => return Err(InvalidBoardState::InvalidPiece { subboard, square, value: piece }),
+                };
+            }
+        }
+        let turn = match ffi_board_state.turn {
+             1 => RawTurn::Cross,
+            -1 => RawTurn::Dot,
+             _ => return Err(InvalidBoardState::InvalidTurn(ffi_board_state.turn)),
+        };
+        let active_subboard = match ffi_board_state.current {
+            -1 => RawActiveSubBoard::All,
+             0 => RawActiveSubBoard::TopLef,
+             1 => RawActiveSubBoard::TopMid,
+             2 => RawActiveSubBoard::TopRig,
+             3 => RawActiveSubBoard::MidLef,
+             4 => RawActiveSubBoard::MidMid,
+             5 => RawActiveSubBoard::MidRig,
+             6 => RawActiveSubBoard::BotLef,
+             7 => RawActiveSubBoard::BotMid,
+             8 => RawActiveSubBoard::BotRig,
+             _ => return Err(InvalidBoardState::InvalidActiveSubboard(ffi_board_state.current)),
+        };
+        Ok(RawBoardState {
+            board,
+            turn,
+            active_subboard,
+        })
+    }
+}
+
+impl From<RawBoardState> for FfiBoardState {
+    fn from(raw_board_state: RawBoardState) -> Self {
+        FfiBoardState {
+            board:   raw_board_state.board.map(|pattern| pattern.map(|piece| piece as i32)),
+            turn:    match raw_board_state.turn {
+                RawTurn::Cross        =>  1,
+                RawTurn::Dot          => -1,
+                RawTurn::Empty(never) => match never {},
+            },
+            current: raw_board_state.active_subboard as i32,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// A small xorshift64* generator, seeded explicitly so that games and
+// searches can be reproduced.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Scrambled with splitmix64 so that close seeds give unrelated streams.
+        let (_, state) = splitmix64(seed);
+
+        // The state must never be zero.
+        Self {
+            state: if state == 0 { 0x2545_f491_4f6c_dd1d } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // A number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            panic!("invalid bound");
+        }
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as usize
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+// One step of splitmix64, returning the next state and its output.
+pub(crate) const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn reproducible() {
+        let mut rng1 = Rng::new(7);
+        let mut rng2 = Rng::new(7);
+        let mut rng3 = Rng::new(8);
+        let numbers1 = (0..16).map(|_| rng1.next_u64()).collect::<Vec<_>>();
+        let numbers2 = (0..16).map(|_| rng2.next_u64()).collect::<Vec<_>>();
+        let numbers3 = (0..16).map(|_| rng3.next_u64()).collect::<Vec<_>>();
+        assert_eq!(numbers1, numbers2);
+        assert_ne!(numbers1, numbers3);
+
+        // Seeds differing only in their lowest bit.
+        let mut rng4 = Rng::new(6);
+        let numbers4 = (0..16).map(|_| rng4.next_u64()).collect::<Vec<_>>();
+        assert_ne!(numbers1, numbers4);
+    }
+
+    #[test]
+    fn below() {
+        let mut rng = Rng::new(0);
+        let mut counts = [0; 9];
+        for _ in 0..9_000 {
+            counts[rng.below(9)] += 1;
+        }
+        assert!(counts.iter().all(|count| (800..1_200).contains(count)));
+        assert_eq!(rng.below(1), 0);
+    }
+}
@@ -0,0 +1,80 @@
+use super::{bitboard::Bitboard, rng::splitmix64, Place, Player};
+
+// Random keys for Zobrist hashing, generated at compile time so that keys are
+// stable between runs and builds.
+const SEED: u64 = 0x7474_745f_6d61_7374;
+
+const fn generate_keys<const N: usize>(mut state: u64) -> ([u64; N], u64) {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        let (new_state, key) = splitmix64(state);
+        state = new_state;
+        keys[i] = key;
+        i += 1;
+    }
+    (keys, state)
+}
+
+const PIECE_KEYS_CROSS: ([u64; 81], u64) = generate_keys(SEED);
+const PIECE_KEYS_DOT:   ([u64; 81], u64) = generate_keys(PIECE_KEYS_CROSS.1);
+const ACTIVE_KEYS:      ([u64;  9], u64) = generate_keys(PIECE_KEYS_DOT.1);
+const TURN_KEY:         ([u64;  1], u64) = generate_keys(ACTIVE_KEYS.1);
+
+pub(super) fn piece_key(player: Player, subboard: Place, square: Place) -> u64 {
+    let index = subboard.to_index() * 9 + square.to_index();
+    match player {
+        Player::Cross => PIECE_KEYS_CROSS.0[index],
+        Player::Dot   => PIECE_KEYS_DOT  .0[index],
+    }
+}
+
+pub(super) fn active_key(active: Bitboard) -> u64 {
+    active
+        .places()
+        .fold(0, |key, subboard| key ^ ACTIVE_KEYS.0[subboard.to_index()])
+}
+
+// Only included while dot is to move.
+pub(super) fn turn_key(turn: Player) -> u64 {
+    match turn {
+        Player::Cross => 0,
+        Player::Dot   => TURN_KEY.0[0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::utils::{bitboard::Bitboard, Place, Player};
+
+    use super::{active_key, piece_key, turn_key};
+
+    #[test]
+    fn keys_distinct() {
+        let mut keys = HashSet::new();
+        for player in [Player::Cross, Player::Dot] {
+            for subboard in 0..9 {
+                for square in 0..9 {
+                    keys.insert(piece_key(player, Place::from_index(subboard), Place::from_index(square)));
+                }
+            }
+        }
+        for subboard in 0..9 {
+            keys.insert(active_key(Bitboard::from_place(Place::from_index(subboard))));
+        }
+        keys.insert(turn_key(Player::Dot));
+        assert_eq!(keys.len(), 2 * 81 + 9 + 1);
+        assert!(!keys.contains(&0));
+    }
+
+    #[test]
+    fn active_key_xor() {
+        let top_lef = Bitboard::from_place(Place::TopLef);
+        let bot_rig = Bitboard::from_place(Place::BotRig);
+        assert_eq!(active_key(top_lef | bot_rig), active_key(top_lef) ^ active_key(bot_rig));
+        assert_eq!(active_key(Bitboard::EMPTY), 0);
+        assert_eq!(turn_key(Player::Cross), 0);
+    }
+}